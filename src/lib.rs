@@ -0,0 +1,3 @@
+pub mod logger;
+pub mod roles;
+pub mod network;
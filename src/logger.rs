@@ -0,0 +1,131 @@
+use crate::roles::coordinator::{Stage};
+
+use std::collections::{HashSet};
+use std::fmt;
+use std::io::{Write};
+use std::sync::{OnceLock};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl Level {
+    pub fn parse(level: &str) -> Option<Level> {
+        match level.to_lowercase().as_str() {
+            "error" => Some(Level::Error),
+            "warn" => Some(Level::Warn),
+            "info" => Some(Level::Info),
+            "debug" => Some(Level::Debug),
+            "trace" => Some(Level::Trace),
+            _ => None,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Level::Error => "error",
+            Level::Warn => "warn",
+            Level::Info => "info",
+            Level::Debug => "debug",
+            Level::Trace => "trace",
+        }
+    }
+}
+
+// records pass when they are at most as verbose as level; node and round filters only
+// drop records that carry a node id or a round
+#[derive(Clone, Debug)]
+pub struct LogFilter {
+    pub level: Level,
+    pub nodes: Option<HashSet<usize>>,
+    pub rounds: Option<(usize, usize)>, //first round, end round exclusive
+}
+
+impl LogFilter {
+    // "17" or "17,18,40"
+    pub fn parse_nodes(nodes: &str) -> Result<HashSet<usize>, String> {
+        nodes.split(',')
+            .map(|n| n.trim().parse::<usize>().map_err(|_| format!("invalid node id {}", n)))
+            .collect()
+    }
+
+    // "3", "3..5" (exclusive) or "3..=5", a range without rounds is an error
+    pub fn parse_rounds(rounds: &str) -> Result<(usize, usize), String> {
+        let parse = |r: &str| r.trim().parse::<usize>().map_err(|_| format!("invalid round range {}", rounds));
+        let (first, end) = if let Some(index) = rounds.find("..=") {
+            (parse(&rounds[..index])?, parse(&rounds[index + 3..])? + 1)
+        } else if let Some(index) = rounds.find("..") {
+            (parse(&rounds[..index])?, parse(&rounds[index + 2..])?)
+        } else {
+            let round = parse(rounds)?;
+            (round, round + 1)
+        };
+        if first >= end {
+            return Err(format!("empty round range {}", rounds));
+        }
+        Ok((first, end))
+    }
+
+    pub fn is_enabled(&self, level: Level, round: Option<usize>, node: Option<usize>) -> bool {
+        if level > self.level {
+            return false;
+        }
+        if let (Some(nodes), Some(node)) = (self.nodes.as_ref(), node) {
+            if !nodes.contains(&node) {
+                return false;
+            }
+        }
+        if let (Some((first, end)), Some(round)) = (self.rounds, round) {
+            if round < first || round >= end {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+static FILTER: OnceLock<LogFilter> = OnceLock::new();
+
+// logging stays off unless a filter is installed, only the first call has an effect
+pub fn init(filter: LogFilter) {
+    let _ = FILTER.set(filter);
+}
+
+pub fn is_enabled(level: Level, round: Option<usize>, node: Option<usize>) -> bool {
+    match FILTER.get() {
+        Some(filter) => filter.is_enabled(level, round, node),
+        None => false,
+    }
+}
+
+// one line of key=value fields on stderr, stdout is left to the run summary
+pub fn write(level: Level, round: Option<usize>, node: Option<usize>, stage: Option<Stage>, kind: &str, message: fmt::Arguments) {
+    let mut line = format!("level={}", level.name());
+    if let Some(round) = round {
+        line.push_str(&format!(" round={}", round));
+    }
+    match node {
+        Some(node) => line.push_str(&format!(" node={}", node)),
+        None => line.push_str(" node=coordinator"),
+    }
+    if let Some(stage) = stage {
+        line.push_str(&format!(" stage={:?}", stage));
+    }
+    line.push_str(&format!(" kind={} {}\n", kind, message));
+    let _ = std::io::stderr().write_all(line.as_bytes());
+}
+
+// log_event!(level, round, node, stage, kind, "format", args..), node None stands for the coordinator
+#[macro_export]
+macro_rules! log_event {
+    ($level:expr, $round:expr, $node:expr, $stage:expr, $kind:expr, $($arg:tt)+) => {
+        if $crate::logger::is_enabled($level, Some($round), $node) {
+            $crate::logger::write($level, Some($round), $node, $stage, $kind, format_args!($($arg)+));
+        }
+    };
+}
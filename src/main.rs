@@ -1,20 +1,50 @@
-mod roles;
-use std::thread::{self, JoinHandle};
-use roles::node::{Node, Message, NodeResult, CentralMessage};
-use roles::coordinator::{Coordinator};
-use roles::creater::{Creater};
-use roles::verifier::{Verifier};
+use distributed_mis::{logger, network};
+use distributed_mis::roles::node::{Algorithm};
+use distributed_mis::roles::creater::{Creater};
+use distributed_mis::roles::verifier::{Verifier, Violation, ColoringVerifier, MatchingVerifier, RulingVerifier};
+use distributed_mis::roles::exporter::{DotExporter};
+use distributed_mis::roles::animator::{Animator, Layout};
+use distributed_mis::roles::baseline::{Baseline, GreedyOrder};
+use distributed_mis::roles::exact::{ExactSolver};
+use distributed_mis::roles::beeping::{BeepingSimulator};
+use distributed_mis::roles::deterministic::{DeterministicMis};
+use distributed_mis::roles::ruling::{RulingSimulator};
+use distributed_mis::roles::shattering::{ResidualGraph};
+use distributed_mis::roles::dynamic::{DynamicMis, Adjustment};
+use distributed_mis::roles::stream::{UpdateStream, Operation};
+use distributed_mis::roles::stabilizing::{StabilizingSimulator};
+use distributed_mis::roles::scheduler::{Scheduler, Policy, Outcome};
+use distributed_mis::roles::explorer::{Explorer, Link};
+use distributed_mis::roles::byzantine::{Strategy, assign_strategies, byzantine_nodes};
+use distributed_mis::roles::runner::{RunConfig, run_threaded};
+use distributed_mis::roles::coordinator::{Coordinator, RoundState};
+use distributed_mis::roles::tracer::{Tracer, TraceHeader, Endpoint, read_trace};
+use distributed_mis::roles::replayer::{Replayer};
+use distributed_mis::roles::checkpoint::{Checkpoint};
+use distributed_mis::roles::desire::{DesireConfig};
+use distributed_mis::roles::sweep::{Family, Measure, measure, grid};
+use distributed_mis::logger::{Level, LogFilter};
 
 #[macro_use]
 extern crate clap;
-use clap::{Arg, App, SubCommand};
+use clap::{ArgMatches};
+use rand::{Rng, thread_rng};
 
+use std::collections::{HashSet};
 use std::fs::File;
-use std::io::{BufRead, BufReader};
-use std::sync::mpsc::{TryRecvError, Sender, Receiver, channel};
+use std::process::{Command, Child};
 use std::io::{BufWriter, Write, Result};
 use std::time::{Duration, Instant};
 
+// generated graphs get integer weights up to this for the weighted algorithm
+const MAX_WEIGHT: usize = 100;
+
+// a self-stabilizing run gives up after this many rounds
+const MAX_STABILIZING_ROUND: usize = 10000;
+
+// lying nodes may keep their neighbors undecided forever, such a run stops after this many rounds
+const MAX_BYZANTINE_ROUND: usize = 1000;
+
 fn main() -> Result<()> {
     let matches = clap_app!(myapp =>
         (version: "0.0")
@@ -24,47 +54,201 @@ fn main() -> Result<()> {
         (@arg node: -n --node +takes_value "get number of node")
         (@arg degree: -d --degree +takes_value "a parameter for init attempt to create degree (1-degree), but may be more due to connection from other nodes")
         (@arg run: -r --run +takes_value "get number of run")
+        (@arg seed: -s --seed +takes_value "seed of the node random choices, run i uses seed + i, random if absent")
+        (@arg trace: -t --trace +takes_value "record every message delivery and stage transition into this file, run i is suffixed by .i when there are several runs")
+        (@arg baseline: --baseline "also solve with the sequential greedy baselines and report their mis sizes in the summary")
+        (@arg exact: --exact "also compute a maximum independent set and report the approximation ratio in the summary, for graphs up to a few hundred nodes")
+        (@arg exact_branches: --("exact-branches") +takes_value requires[exact] "stop the --exact search after this many branches and report the largest set found as a lower bound, default 100000")
+        (@arg dot: --dot +takes_value "write the graph and its mis as Graphviz DOT into this file, run i is suffixed by .i when there are several runs")
+        (@arg animate: --animate +takes_value "write one svg frame per round and an index.html to step through them into this directory, run i is suffixed by .i when there are several runs")
+        (@arg layout: --layout +takes_value "node coordinates for --animate, one \"id x y\" line per node, force directed layout if absent")
+        (@arg beeping: --beeping "run the beeping model MIS of Jeavons et al. on a simulated beeping channel instead of passing messages")
+        (@arg deterministic: --deterministic "run the randomness free MIS: reduce the node ids to a (max degree + 1)-coloring, then let color classes join one per round")
+        (@arg distance: --distance +takes_value "compute an mis of the graph power G^k instead, no two chosen nodes within distance k and every node within distance k of one, nodes relay messages k hops, on the lockstep ruling set simulator")
+        (@arg ruling: --ruling +takes_value "compute an (alpha, beta)-ruling set given as alpha,beta: chosen nodes at distance alpha or more and every node within distance beta of one, beta at least alpha - 1, on the lockstep ruling set simulator")
+        (@arg shatter: --shatter +takes_value "after this many rounds, report the connected components formed by the undecided nodes, mis algorithms only")
+        (@arg shatter_finish: --("shatter-finish") requires[shatter] "stop the run at the --shatter snapshot and finish the undecided nodes with the deterministic mis")
+        (@arg dynamic: --dynamic +takes_value "after solving, apply this many random edge and node insertions and deletions, repairing the mis around each one with the distributed algorithm")
+        (@arg per_update: --("per-update") requires[dynamic] "with --dynamic, print the nodes affected and changed and the repair rounds of every update")
+        (@arg stabilizing: --stabilizing "run the self-stabilizing MIS, starting from corrupted node states")
+        (@arg faults: --faults +takes_value "with --stabilizing, corrupt node states this many times once stable, and measure the rounds to stabilize again")
+        (@arg fault_rate: --("fault-rate") +takes_value "probability that a fault corrupts each node, default 0.1")
+        (@arg byzantine: --byzantine +takes_value "make this many random nodes byzantine, they lie to their neighbors with --strategy and the mis is only verified on the honest nodes, mis algorithms only")
+        (@arg strategy: --strategy +takes_value requires[byzantine] "how byzantine nodes lie: marked (default) always claims to be marked, desire claims a desire level of 2, joined claims to have joined the mis")
+        (@arg schedule: --schedule +takes_value conflicts_with[byzantine shatter checkpoint resume beeping deterministic distance ruling stabilizing dynamic] "deliver the messages one at a time in a single thread, in the order of this policy: random, fifo, lifo, starve:id, which delays messages to node id, or delay:u-v,..., which delays messages on these edges, mis algorithms only")
+        (@arg max_rounds: --("max-rounds") +takes_value conflicts_with[shatter beeping deterministic distance ruling stabilizing dynamic] "stop after this many rounds and report the partial mis with the undecided nodes, mis algorithms only")
+        (@arg time_limit: --("time-limit") +takes_value conflicts_with[shatter trace beeping deterministic distance ruling stabilizing dynamic schedule] "stop after this many seconds, even in the middle of a round, and report the partial mis with the undecided nodes, mis algorithms only")
+        (@arg greedy_fallback: --("greedy-fallback") conflicts_with[beeping deterministic distance ruling stabilizing dynamic] "when --max-rounds or --time-limit stops the run, complete the undecided nodes with the sequential greedy mis, the summary reports the nodes added centrally")
+        (@arg checkpoint: --checkpoint +takes_value conflicts_with[trace byzantine shatter beeping deterministic distance ruling stabilizing dynamic] "write the simulation state into this file between rounds, so that --resume continues from there, run i is suffixed by .i when there are several runs, mis algorithms only")
+        (@arg checkpoint_every: --("checkpoint-every") +takes_value requires[checkpoint] "rounds between two checkpoints, default 10")
+        (@arg resume: --resume +takes_value conflicts_with[graph node trace byzantine shatter beeping deterministic distance ruling stabilizing dynamic] "continue the run saved in this checkpoint file, with its graph, seed and algorithm, the result is the one of the uninterrupted run")
+        (@arg algorithm: --algorithm +takes_value +global "luby (default), metivier, which exchanges single random bits and reports the number of bits sent, coloring, which solves (max degree + 1)-coloring instead of MIS, matching, which solves maximal matching, or weighted, which favors heavy nodes, weights being given as id:weight in the first column of the graph file")
+        (@arg desire: --desire +takes_value +global "desire level parameters of luby and weighted as key=value pairs separated by commas: initial, up, down, cap, threshold and floor, e.g. up=3,floor=0.01, over the values of --desire-config")
+        (@arg desire_config: --("desire-config") +takes_value +global "file of key = value lines setting the desire level parameters, the defaults being initial 0.5, up 2, down 2, cap 0.5, threshold 2 and floor none")
+        (@arg log_level: --("log-level") +takes_value +global "log to stderr at level error, warn, info, debug or trace, default info when a log filter is given")
+        (@arg log_node: --("log-node") +takes_value +global "only log these node ids, e.g. 17 or 17,18")
+        (@arg log_round: --("log-round") +takes_value +global "only log these rounds, e.g. 3, 3..5 (end exclusive) or 3..=5")
+        (@subcommand coordinator =>
+            (about: "run the coordinator, waiting for node processes to connect over TCP")
+            (@arg graph: -g --graph +takes_value +required "graph file path")
+            (@arg processes: -p --processes +takes_value +required "number of node processes")
+            (@arg port: --port +takes_value "TCP port to listen on, default 7000")
+            (@arg dot: --dot +takes_value "write the graph and its mis as Graphviz DOT into this file")
+        )
+        (@subcommand node =>
+            (about: "run a subset of nodes in this process, nodes are dealt round robin among processes")
+            (@arg graph: -g --graph +takes_value +required "graph file path")
+            (@arg index: -i --index +takes_value +required "index of this process, starting at 0")
+            (@arg processes: -p --processes +takes_value +required "number of node processes")
+            (@arg addr: -a --addr +takes_value "coordinator address, default 127.0.0.1:7000")
+        )
+        (@subcommand launch =>
+            (about: "start a coordinator and node processes on localhost")
+            (@arg graph: -g --graph +takes_value "graph file path, a random graph is created if absent")
+            (@arg node: -n --node +takes_value "number of node of the random graph")
+            (@arg degree: -d --degree +takes_value "degree parameter of the random graph")
+            (@arg processes: -p --processes +takes_value +required "number of node processes")
+            (@arg port: --port +takes_value "TCP port of the coordinator, default 7000")
+        )
+        (@subcommand dynamic =>
+            (about: "apply a stream of timestamped updates to the mis of a graph, checking the mis after every batch")
+            (@arg graph: -g --graph +takes_value +required "graph file path")
+            (@arg stream: -s --stream +takes_value +required "update stream file path")
+            (@arg generate: --generate +takes_value "first write a random stream of this many batches into the stream file")
+            (@arg batch_size: --("batch-size") +takes_value "updates per generated batch, default 10")
+            (@arg seed: --seed +takes_value "seed of the distributed repairs")
+            (@arg per_update: --("per-update") "print the nodes affected and changed and the repair rounds of every update")
+        )
+        (@subcommand explore =>
+            (about: "explore the message delivery orders of a small graph with fixed random choices, looking for deadlocks, panics and wrong mis")
+            (@arg graph: -g --graph +takes_value +required "graph file path")
+            (@arg seed: -s --seed +takes_value "seed of the node random choices, default 0")
+            (@arg max_executions: --("max-executions") +takes_value "stop after this many executions, default 100000")
+            (@arg trace: -t --trace +takes_value "record the first anomalous execution into this file, for replay")
+        )
+        (@subcommand sweep =>
+            (about: "measure the rounds of luby or weighted for every combination of desire level parameters on families of graphs, and report the setting with the fewest")
+            (@arg family: --family +takes_value ... "a family of random graphs given as nodes:degree, e.g. 1000:10")
+            (@arg graph: -g --graph +takes_value ... "a graph file, its family runs it with different seeds")
+            (@arg vary: --vary +takes_value ... "the values of one parameter, e.g. up=1.5,2,3 or floor=none,0.01, other parameters keep the value of --desire")
+            (@arg cases: --cases +takes_value "runs per family, a new graph or seed each, default 10")
+            (@arg seed: -s --seed +takes_value "seed of the first case, case i uses seed + i, default 0")
+            (@arg max_rounds: --("max-rounds") +takes_value "a run still undecided after this many rounds counts as unfinished, default 1000")
+        )
+        (@subcommand replay =>
+            (about: "re-execute a recorded trace step by step and report the first divergence")
+            (@arg trace: -t --trace +takes_value +required "trace file path")
+            (@arg verbose: -v --verbose "print every replayed event")
+        )
     )
     .get_matches();
 
+    init_logger(&matches)?;
+
+    match matches.subcommand() {
+        ("coordinator", Some(m)) => return coordinator_process(m),
+        ("node", Some(m)) => return node_process(m),
+        ("launch", Some(m)) => return launch(m),
+        ("replay", Some(m)) => return replay(m),
+        ("dynamic", Some(m)) => return dynamic_stream(m),
+        ("explore", Some(m)) => return explore(m),
+        ("sweep", Some(m)) => return sweep(m),
+        _ => (),
+    }
+
     let graph_path = matches.value_of("graph");
-    let mut input_num_node = matches.value_of("node");
-    let mut input_num_degree = matches.value_of("degree");
-    let mut input_num_run = matches.value_of("run");
+    let input_num_node = matches.value_of("node");
+    let input_num_degree = matches.value_of("degree");
+    let input_num_run = matches.value_of("run");
     //println!("graph  path {:?}", graph_path);
     //println!("num_node {:?}", input_num_node);
     //println!("num_degree {:?}", input_num_degree);
     //println!("num_run {:?}", input_num_run);
 
     
-    let mut num_node = 0;
-    let mut num_degree = 0;
     let mut num_run = 1000;
     let mut graph_creater = Creater::new();
+    let mut options = RunOptions {
+        seed: value_t!(matches, "seed", u64).ok(),
+        trace: matches.value_of("trace").map(|t| t.to_string()),
+        dot: matches.value_of("dot").map(|d| d.to_string()),
+        animate: matches.value_of("animate").map(|a| a.to_string()),
+        baseline: matches.is_present("baseline"),
+        exact: matches.is_present("exact"),
+        exact_branches: value_t!(matches, "exact_branches", usize).unwrap_or(100000),
+        algorithm: parse_algorithm(&matches)?,
+        desire: parse_desire(&matches)?,
+        beeping: matches.is_present("beeping"),
+        deterministic: matches.is_present("deterministic"),
+        ruling: parse_ruling(&matches)?,
+        shatter: value_t!(matches, "shatter", usize).ok(),
+        shatter_finish: matches.is_present("shatter_finish"),
+        per_update: matches.is_present("per_update"),
+        dynamic: value_t!(matches, "dynamic", usize).ok(),
+        stabilizing: matches.is_present("stabilizing"),
+        num_fault: value_t!(matches, "faults", usize).unwrap_or(0),
+        fault_rate: value_t!(matches, "fault_rate", f64).unwrap_or(0.1),
+        byzantine: parse_byzantine(&matches)?,
+        schedule: matches.value_of("schedule").map(Policy::parse).transpose().map_err(invalid_input)?,
+        layout: matches.value_of("layout").map(|l| l.to_string()),
+        max_rounds: value_t!(matches, "max_rounds", usize).ok(),
+        time_limit: value_t!(matches, "time_limit", f64).ok(),
+        greedy_fallback: matches.is_present("greedy_fallback"),
+        checkpoint: matches.value_of("checkpoint").map(|c| c.to_string()),
+        checkpoint_every: value_t!(matches, "checkpoint_every", usize).unwrap_or(10),
+        resume: None,
+        num_run,
+        run: 0,
+    };
+
+    if let Some(path) = matches.value_of("resume") {
+        let checkpoint = Checkpoint::load(path)?;
+        let graph_creater = Creater {
+            graph: checkpoint.graph.clone(),
+            weights: checkpoint.weights.clone(),
+        };
+        options.seed = Some(checkpoint.seed);
+        options.algorithm = checkpoint.algorithm;
+        options.desire = checkpoint.desire;
+        options.num_run = 1;
+        options.resume = Some(checkpoint);
+        solve_graph(&graph_creater.graph, &graph_creater.weights, graph_creater.get_max_degree(), &options)?;
+        return Ok(());
+    }
 
-    if graph_path.is_some() {
-        for _ in 0..num_run {
-            input_file_graph(graph_path.unwrap());
+    if let Some(graph_path) = graph_path {
+        if let Some(input_num_run) = input_num_run {
+            num_run = input_num_run.parse::<usize>().unwrap();
+        }
+        options.num_run = num_run;
+        for run in 0..num_run {
+            options.run = run;
+            input_file_graph(graph_path, &options)?;
         }
         return Ok(());
     } 
-    num_node = input_num_node.unwrap().parse::<usize>().unwrap();
-    num_degree = input_num_degree.unwrap().parse::<usize>().unwrap(); 
+    let num_node = input_num_node.unwrap().parse::<usize>().unwrap();
+    let num_degree = input_num_degree.unwrap().parse::<usize>().unwrap(); 
     num_run = input_num_run.unwrap().parse::<usize>().unwrap(); 
+    options.num_run = num_run;
     
     let filename = format!("n{}_d{}_r{}", num_node, num_degree, num_run);
     let f = File::create(filename).expect("Unable to open file");
     let mut f = BufWriter::new(f);    
 
-    for _ in 0..num_run {
+    for run in 0..num_run {
+        options.run = run;
         let graph = graph_creater.generate(num_node, num_degree);
-        graph_creater.store_graph("graph".to_string()); 
+        if options.algorithm == Algorithm::Weighted {
+            graph_creater.random_weights(MAX_WEIGHT);
+        }
+        graph_creater.store_graph("graph".to_string())?; 
         //println!("created graph");
         let max_degree = graph_creater.get_max_degree();
         //
-        let (summary, result) = solve_graph(&graph, num_node, max_degree);
-        f.write_all(summary.as_bytes());
-        if !result {
+        let (summary, result) = solve_graph(&graph, &graph_creater.weights, max_degree, &options)?;
+        f.write_all(summary.as_bytes())?;
+        if !result && options.byzantine.is_none() {
             panic!("result is false");
         }
         
@@ -72,47 +256,280 @@ fn main() -> Result<()> {
     Ok(())
 }
 
-fn solve_graph(graph: &Vec<Vec<usize>>, num_node: usize, max_degree: usize) -> (String, bool) {
-    let now = Instant::now();
-    let (central_sender, central_receiver) = channel();
-    let mut node_list: Vec<Node> = vec![];
-    let mut sender_list: Vec<Sender<Message>> = vec![];
-    let mut join_handlers = vec![];
+fn invalid_input(message: String) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidInput, message)
+}
 
-    // initialize nodes
-    for i in 0..num_node {
-        let (node, sender_to_node) = Node::new(i, central_sender.clone()); 
-        node_list.push(node);
-        sender_list.push(sender_to_node);
+// subcommands see the global algorithm option in their own matches
+fn parse_algorithm(matches: &ArgMatches) -> Result<Algorithm> {
+    let sub_matches = match matches.subcommand() {
+        (_, Some(m)) => m,
+        _ => matches,
+    };
+    match sub_matches.value_of("algorithm") {
+        Some(algorithm) => Algorithm::parse(algorithm).ok_or_else(|| invalid_input(format!("unknown algorithm {}", algorithm))),
+        None => Ok(Algorithm::Luby),
+    }
+}
+
+// --desire settings apply over the --desire-config file, both over the defaults
+fn parse_desire(matches: &ArgMatches) -> Result<DesireConfig> {
+    let sub_matches = match matches.subcommand() {
+        (_, Some(m)) => m,
+        _ => matches,
+    };
+    let mut desire = match sub_matches.value_of("desire_config") {
+        Some(path) => DesireConfig::load(path)?,
+        None => DesireConfig::default(),
+    };
+    if let Some(settings) = sub_matches.value_of("desire") {
+        desire.apply(settings).map_err(invalid_input)?;
+    }
+    Ok(desire)
+}
+
+// --distance k is the (k + 1, k)-ruling set
+fn parse_ruling(matches: &ArgMatches) -> Result<Option<(usize, usize)>> {
+    if matches.is_present("distance") {
+        let k = value_t!(matches, "distance", usize).unwrap_or_else(|e| e.exit());
+        return Ok(Some((k + 1, k)));
+    }
+    let ruling = match matches.value_of("ruling") {
+        Some(ruling) => ruling,
+        None => return Ok(None),
+    };
+    let bounds = ruling.split(',')
+        .map(|b| b.trim().parse::<usize>())
+        .collect::<std::result::Result<Vec<usize>, _>>()
+        .map_err(|_| invalid_input(format!("invalid ruling set {}", ruling)))?;
+    match bounds.as_slice() {
+        [alpha, beta] => Ok(Some((*alpha, *beta))),
+        _ => Err(invalid_input(format!("ruling set {} is not alpha,beta", ruling))),
+    }
+}
+
+fn parse_byzantine(matches: &ArgMatches) -> Result<Option<(usize, Strategy)>> {
+    let num_byzantine = match value_t!(matches, "byzantine", usize) {
+        Ok(num_byzantine) => num_byzantine,
+        Err(_) => return Ok(None),
+    };
+    let strategy = match matches.value_of("strategy") {
+        Some(strategy) => Strategy::parse(strategy).ok_or_else(|| invalid_input(format!("unknown strategy {}", strategy)))?,
+        None => Strategy::AlwaysMarked,
+    };
+    Ok(Some((num_byzantine, strategy)))
+}
+
+// logging is off unless one of the log options is given
+fn init_logger(matches: &ArgMatches) -> Result<()> {
+    let sub_matches = match matches.subcommand() {
+        (_, Some(m)) => m,
+        _ => matches,
+    };
+    let log_level = sub_matches.value_of("log_level");
+    let log_node = sub_matches.value_of("log_node");
+    let log_round = sub_matches.value_of("log_round");
+    if log_level.is_none() && log_node.is_none() && log_round.is_none() {
+        return Ok(());
+    }
+    let level = match log_level {
+        Some(level) => Level::parse(level).ok_or_else(|| invalid_input(format!("unknown log level {}", level)))?,
+        None => Level::Info,
+    };
+    let nodes = log_node.map(LogFilter::parse_nodes).transpose().map_err(invalid_input)?;
+    let rounds = log_round.map(LogFilter::parse_rounds).transpose().map_err(invalid_input)?;
+    logger::init(LogFilter { level, nodes, rounds });
+    Ok(())
+}
+
+// settings of the current run taken from the command line
+struct RunOptions {
+    seed: Option<u64>,
+    trace: Option<String>,
+    dot: Option<String>,
+    animate: Option<String>,
+    layout: Option<String>,
+    baseline: bool,
+    exact: bool,
+    exact_branches: usize,
+    algorithm: Algorithm,
+    desire: DesireConfig,
+    beeping: bool,
+    deterministic: bool,
+    ruling: Option<(usize, usize)>, //alpha, beta
+    shatter: Option<usize>,
+    shatter_finish: bool,
+    per_update: bool, //one line per dynamic update
+    dynamic: Option<usize>, //random updates
+    stabilizing: bool,
+    num_fault: usize,
+    fault_rate: f64,
+    byzantine: Option<(usize, Strategy)>, //number of byzantine nodes, how they lie
+    schedule: Option<Policy>,
+    max_rounds: Option<usize>,
+    time_limit: Option<f64>, //seconds
+    greedy_fallback: bool, //completes a stopped run centrally
+    checkpoint: Option<String>,
+    checkpoint_every: usize,
+    resume: Option<Checkpoint>,
+    num_run: usize,
+    run: usize,
+}
+
+impl RunOptions {
+    fn run_seed(&self) -> u64 {
+        match self.seed {
+            Some(seed) => seed.wrapping_add(self.run as u64),
+            None => thread_rng().gen(),
+        }
     }
 
-    // connect nodes
-    for i in 0..num_node{
-        for n_id in graph[i].iter() {
-            node_list[*n_id].register_neighbor(i, sender_list[i].clone());
-            node_list[i].register_neighbor(*n_id, sender_list[*n_id].clone());
+    // output files are suffixed by the run index when there are several runs
+    fn output_path(&self, path: &Option<String>) -> Option<String> {
+        match path.as_ref() {
+            Some(path) if self.num_run > 1 => Some(format!("{}.{}", path, self.run)),
+            Some(path) => Some(path.clone()),
+            None => None,
         }
     }
+}
 
-    let mut coordinator = Coordinator::new(sender_list, central_receiver);
+fn solve_graph(graph: &[Vec<usize>], weights: &[f32], max_degree: usize, options: &RunOptions) -> Result<(String, bool)> {
+    if options.beeping {
+        return solve_beeping(graph, max_degree, options);
+    }
+    if options.deterministic {
+        return solve_deterministic(graph, max_degree, options);
+    }
+    if let Some((alpha, beta)) = options.ruling {
+        return solve_ruling(graph, max_degree, alpha, beta, options);
+    }
+    if options.stabilizing {
+        return solve_stabilizing(graph, max_degree, options);
+    }
+    if let Some(num_update) = options.dynamic {
+        return solve_dynamic(graph, weights, max_degree, num_update, options);
+    }
+    if let Some(policy) = options.schedule.as_ref() {
+        return solve_scheduled(graph, weights, max_degree, policy, options);
+    }
+    let now = Instant::now();
+    let num_node = graph.len();
+    let seed = options.run_seed();
+    let is_mis = !matches!(options.algorithm, Algorithm::Coloring | Algorithm::Matching);
+    if !is_mis && (options.time_limit.is_some() || options.greedy_fallback) {
+        return Err(invalid_input(format!("--time-limit and --greedy-fallback stop an mis run, not a {}", options.algorithm.name())));
+    }
+    if !is_mis && (options.checkpoint.is_some() || options.resume.is_some()) {
+        return Err(invalid_input(format!("checkpoints hold an mis run, not a {}", options.algorithm.name())));
+    }
+    if !is_mis && options.byzantine.is_some() {
+        return Err(invalid_input(format!("byzantine nodes lie in an mis run, not in a {}", options.algorithm.name())));
+    }
+    if !is_mis && options.shatter.is_some() {
+        return Err(invalid_input(format!("--shatter reports the undecided nodes of an mis run, not of a {}", options.algorithm.name())));
+    }
+    let strategies = match options.byzantine {
+        Some((num_byzantine, strategy)) => assign_strategies(num_node, num_byzantine, strategy, seed),
+        None => vec![None; num_node],
+    };
+    let byzantine = byzantine_nodes(&strategies);
+    let stop_round = match options.shatter {
+        Some(round) if options.shatter_finish => Some(round),
+        None if options.max_rounds.is_some() => options.max_rounds,
+        None if !byzantine.is_empty() => Some(MAX_BYZANTINE_ROUND),
+        _ => None,
+    };
+    let tracer = match options.output_path(&options.trace) {
+        Some(path) => Some(Tracer::create(&path, &TraceHeader {
+            seed,
+            algorithm: options.algorithm,
+            graph: graph.to_vec(),
+            weights: weights.to_vec(),
+            stop_round,
+            strategies: strategies.clone(),
+            desire: options.desire,
+        })?),
+        None => None,
+    };
 
-    // start simulation
-    while let Some(node) = node_list.pop() {
-        join_handlers.push(node.start()); 
+    let mut config = RunConfig::new(options.algorithm, seed);
+    config.desire = options.desire;
+    config.strategies = strategies.clone();
+    config.tracer = tracer.as_ref().map(|tracer| tracer.sender.clone());
+    config.resume = options.resume.clone();
+    config.time_limit = options.time_limit.map(Duration::from_secs_f64);
+    config.checkpoint = options.output_path(&options.checkpoint).map(|path| (path, options.checkpoint_every));
+    config.snapshot = match options.shatter {
+        Some(round) => Some((round, options.shatter_finish)),
+        None => stop_round.map(|round| (round, true)),
+    };
+    let outcome = run_threaded(graph, weights, &config);
+    let solve_time = outcome.solve_time;
+    let mut coordinator = outcome.coordinator;
+    if let Some(tracer) = tracer {
+        tracer.finish()?;
     }
-    let solve_now = Instant::now();
-    coordinator.start();
-    let solve_time = solve_now.elapsed().as_millis();
     // wait for result
     
-    let mis = coordinator.get_mis_result();
+    let mut mis = coordinator.get_mis_result();
     //println!("mis {:?}", mis);
 
     let build_and_solve = now.elapsed().as_millis();
-    
-    let mut verifier = Verifier::new(&mis, &graph);
-    let result = verifier.verify();
+    if options.algorithm == Algorithm::Coloring {
+        let verifier = ColoringVerifier::new(&coordinator.colors, graph);
+        let summary = summarize_coloring(&verifier, coordinator.round, (build_and_solve, solve_time));
+        println!("{}", summary);
+        return Ok((summary, verifier.verify()));
+    }
+    if options.algorithm == Algorithm::Matching {
+        let verifier = MatchingVerifier::new(&coordinator.partners, graph);
+        let summary = summarize_matching(&verifier, coordinator.round, max_degree, (build_and_solve, solve_time));
+        println!("{}", summary);
+        return Ok((summary, verifier.verify()));
+    }
+
+    let mut shatter = String::new();
+    if let Some(shatter_round) = options.shatter {
+        let residual_graph = ResidualGraph::new(graph, coordinator.residual.as_deref().unwrap_or(&[]));
+        shatter = shatter_summary(shatter_round, &residual_graph);
+        if coordinator.is_stopping && coordinator.residual.is_some() {
+            let (finished, finish_round) = residual_graph.finish_deterministic();
+            let round = coordinator.round;
+            for id in residual_graph.nodes.iter() {
+                coordinator.decide_round[*id] = Some(round);
+            }
+            if let Some(state) = coordinator.history.last_mut() {
+                state.joined.extend(residual_graph.nodes.iter().filter(|id| finished.contains(id)));
+                state.removed.extend(residual_graph.nodes.iter().filter(|id| !finished.contains(id)));
+            }
+            shatter.push_str(&format!("finishRounds {} finishSize {} ", finish_round, finished.len()));
+            mis.extend(finished);
+        }
+    }
+
+    let (stop, undecided) = if is_mis {
+        stop_summary(graph, &mut coordinator, &mut mis, options)
+    } else {
+        (String::new(), vec![])
+    };
+
+    // a stopped run is right when only its undecided nodes are left uncovered
+    let verifier = Verifier::new(&mis, graph);
+    let violations = verifier.find_honest_violations(&byzantine);
+    let result = violations.iter().all(|violation| matches!(violation, Violation::Maximality(id) if undecided.contains(id)));
     let round = coordinator.round;
+    let num_bit = coordinator.num_bit;
+    let num_checkpoint = (coordinator.num_checkpoint, coordinator.num_failed_checkpoint);
+    write_outputs(graph, &verifier.mis, &violations, &coordinator.decide_round, &coordinator.history, options)?;
+    let mut lies = String::new();
+    if !byzantine.is_empty() {
+        let undecided = match options.shatter {
+            Some(_) => 0,
+            None => coordinator.residual.as_ref().map_or(0, |residual| residual.len()),
+        };
+        lies = byzantine_summary(graph, weights, &strategies, (round, &mis, undecided), &violations, seed, options);
+    }
 
     //println!("graph {:?}", graph);
     //println!("Result {}", result);
@@ -131,38 +548,797 @@ fn solve_graph(graph: &Vec<Vec<usize>>, num_node: usize, max_degree: usize) -> (
     //    }
     //}
 
-    let mut summary: String = format!("{} round {} misSize {} maxDeg {} ttime {} stime {} sol {:?} input {:?}\n", 
+    let mut extra = algorithm_summary(options.algorithm, num_bit);
+    extra.push_str(&desire_summary(options.algorithm, &options.desire));
+    if options.algorithm == Algorithm::Weighted {
+        extra.push_str(&weight_summary(graph, weights, &mis));
+    }
+    extra.push_str(&shatter);
+    extra.push_str(&stop);
+    extra.push_str(&lies);
+    if let Some(checkpoint) = options.resume.as_ref() {
+        extra.push_str(&format!("resumedRound {} ", checkpoint.round));
+    }
+    if options.checkpoint.is_some() {
+        extra.push_str(&format!("checkpoints {} failedCheckpoints {} every {} ", num_checkpoint.0, num_checkpoint.1, options.checkpoint_every));
+    }
+    extra.push_str(&comparison_summary(graph, &mis, seed, options));
+    let summary = summarize(result, round, &mis, max_degree, (build_and_solve, solve_time), &extra, graph);
+    println!("{}", summary);
+
+    Ok((summary, result))
+}
+
+// the beeping channel runs in slots, in a single thread
+fn solve_beeping(graph: &[Vec<usize>], max_degree: usize, options: &RunOptions) -> Result<(String, bool)> {
+    let now = Instant::now();
+    let seed = options.run_seed();
+    let mut simulator = BeepingSimulator::new(graph, seed);
+    let solve_now = Instant::now();
+    simulator.run();
+    let solve_time = solve_now.elapsed().as_millis();
+    let build_and_solve = now.elapsed().as_millis();
+
+    let mis = simulator.get_mis_result();
+    let verifier = Verifier::new(&mis, graph);
+    let result = verifier.verify();
+    write_outputs(graph, &verifier.mis, &verifier.find_violations(), &simulator.decide_round, &simulator.history, options)?;
+
+    let mut extra = format!("model beeping slots {} beeps {} ", simulator.channel.num_slot, simulator.channel.num_beep);
+    extra.push_str(&comparison_summary(graph, &mis, seed, options));
+    let round = simulator.phase.saturating_sub(1);
+    let summary = summarize(result, round, &mis, max_degree, (build_and_solve, solve_time), &extra, graph);
+    println!("{}", summary);
+
+    Ok((summary, result))
+}
+
+// the trace of a scheduled run replays like the one of a threaded run
+// the run stopped at --max-rounds or --time-limit leaves undecided nodes, reported or
+// completed with the greedy mis, returns the summary and the nodes still undecided
+fn stop_summary(graph: &[Vec<usize>], coordinator: &mut Coordinator, mis: &mut HashSet<usize>, options: &RunOptions) -> (String, Vec<usize>) {
+    let mut stop = String::new();
+    let mut undecided: Vec<usize> = vec![];
+    let is_stopped = coordinator.is_timed_out || (options.max_rounds.is_some() && coordinator.residual.is_some());
+    if !is_stopped {
+        return (stop, undecided);
+    }
+    undecided = coordinator.residual.clone().unwrap_or_default();
+    let reason = if coordinator.is_timed_out { "timeLimit" } else { "maxRounds" };
+    stop = format!("stopped {} undecided {} ", reason, undecided.len());
+    if options.greedy_fallback {
+        let added = Baseline::new(graph).complete(mis, &undecided);
+        let round = coordinator.round;
+        for id in undecided.iter() {
+            coordinator.decide_round[*id] = Some(round);
+        }
+        if let Some(state) = coordinator.history.last_mut() {
+            state.joined.extend(undecided.iter().filter(|id| added.contains(id)));
+            state.removed.extend(undecided.iter().filter(|id| !added.contains(id)));
+        }
+        stop.push_str(&format!("completedCentrally greedy addedByGreedy {} ", added.len()));
+        mis.extend(added);
+        undecided.clear();
+    } else {
+        stop.push_str(&format!("undecidedNodes {:?} ", undecided));
+    }
+    (stop, undecided)
+}
+
+fn solve_scheduled(graph: &[Vec<usize>], weights: &[f32], max_degree: usize, policy: &Policy, options: &RunOptions) -> Result<(String, bool)> {
+    if matches!(options.algorithm, Algorithm::Coloring | Algorithm::Matching) {
+        return Err(invalid_input(format!("the scheduler checks an mis, not a {}", options.algorithm.name())));
+    }
+    let now = Instant::now();
+    let seed = options.run_seed();
+    let mut scheduler = Scheduler::new(graph, weights, options.algorithm, seed, policy.clone());
+    scheduler.set_desire(options.desire);
+    if let Some(round) = options.max_rounds {
+        scheduler.coordinator.set_snapshot(round, true);
+    }
+    let tracer = match options.output_path(&options.trace) {
+        Some(path) => Some(Tracer::create(&path, &TraceHeader {
+            seed,
+            algorithm: options.algorithm,
+            graph: graph.to_vec(),
+            weights: weights.to_vec(),
+            stop_round: options.max_rounds,
+            strategies: vec![None; graph.len()],
+            desire: options.desire,
+        })?),
+        None => None,
+    };
+    if let Some(tracer) = tracer.as_ref() {
+        scheduler.set_tracer(tracer.sender.clone());
+    }
+    let solve_now = Instant::now();
+    let outcome = scheduler.run();
+    let solve_time = solve_now.elapsed().as_millis();
+    let build_and_solve = now.elapsed().as_millis();
+
+    let mut mis = scheduler.coordinator.get_mis_result();
+    let (stop, undecided) = stop_summary(graph, &mut scheduler.coordinator, &mut mis, options);
+    let coordinator = &scheduler.coordinator;
+    let verifier = Verifier::new(&mis, graph);
+    let violations = verifier.find_violations();
+    let is_covered = violations.iter().all(|violation| matches!(violation, Violation::Maximality(id) if undecided.contains(id)));
+    let result = outcome == Outcome::Finished && is_covered;
+    write_outputs(graph, &verifier.mis, &violations, &coordinator.decide_round, &coordinator.history, options)?;
+
+    let mut extra = algorithm_summary(options.algorithm, coordinator.num_bit);
+    extra.push_str(&desire_summary(options.algorithm, &options.desire));
+    extra.push_str(&stop);
+    extra.push_str(&format!("schedule {} outcome {} deliveries {} maxInFlight {} lost {} ",
+        policy.name(),
+        outcome.name(),
+        scheduler.num_delivery,
+        scheduler.max_in_flight,
+        scheduler.num_lost,
+    ));
+    extra.push_str(&comparison_summary(graph, &mis, seed, options));
+    let summary = summarize(result, coordinator.round, &mis, max_degree, (build_and_solve, solve_time), &extra, graph);
+    println!("{}", summary);
+    // a deadlocked run records no End, the trace is closed once every node is gone
+    drop(scheduler);
+    if let Some(tracer) = tracer {
+        tracer.finish()?;
+    }
+
+    Ok((summary, result))
+}
+
+fn solve_deterministic(graph: &[Vec<usize>], max_degree: usize, options: &RunOptions) -> Result<(String, bool)> {
+    let now = Instant::now();
+    let mut solver = DeterministicMis::new(graph);
+    let solve_now = Instant::now();
+    solver.run();
+    let solve_time = solve_now.elapsed().as_millis();
+    let build_and_solve = now.elapsed().as_millis();
+
+    let mis = solver.get_mis_result();
+    let verifier = Verifier::new(&mis, graph);
+    let result = verifier.verify();
+    write_outputs(graph, &verifier.mis, &verifier.find_violations(), &solver.decide_round, &solver.history, options)?;
+
+    let mut extra = format!("model deterministic colors {} colorRounds {} misRounds {} messages {} ",
+        solver.palette, solver.color_round, solver.mis_round, solver.num_message);
+    extra.push_str(&comparison_summary(graph, &mis, options.run_seed(), options));
+    let round = (solver.color_round + solver.mis_round).saturating_sub(1);
+    let summary = summarize(result, round, &mis, max_degree, (build_and_solve, solve_time), &extra, graph);
+    println!("{}", summary);
+
+    Ok((summary, result))
+}
+
+// rulers are checked against the requested beta, the summary also gives the beta the
+// chosen algorithm guarantees
+fn solve_ruling(graph: &[Vec<usize>], max_degree: usize, alpha: usize, beta: usize, options: &RunOptions) -> Result<(String, bool)> {
+    let now = Instant::now();
+    let mut simulator = RulingSimulator::new(graph, options.run_seed());
+    let solve_now = Instant::now();
+    let guaranteed_beta = simulator.ruling_set(alpha, beta)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+    let solve_time = solve_now.elapsed().as_millis();
+    let build_and_solve = now.elapsed().as_millis();
+
+    let rulers = simulator.get_rulers();
+    let verifier = RulingVerifier::new(&rulers, graph);
+    let result = verifier.verify(alpha, beta);
+    write_outputs(graph, &rulers, &[], &simulator.decide_round, &simulator.history, options)?;
+
+    let extra = format!("model ruling alpha {} beta {} guaranteedBeta {} rounds {} messages {} ",
+        alpha, beta, guaranteed_beta, simulator.num_round, simulator.num_message);
+    let round = simulator.iteration.saturating_sub(1);
+    let summary = summarize(result, round, &rulers, max_degree, (build_and_solve, solve_time), &extra, graph);
+    println!("{}", summary);
+
+    Ok((summary, result))
+}
+
+// every node starts corrupted, then faults hit once the mis is stable, a run that does
+// not stabilize within MAX_STABILIZING_ROUND rounds fails
+fn solve_stabilizing(graph: &[Vec<usize>], max_degree: usize, options: &RunOptions) -> Result<(String, bool)> {
+    let now = Instant::now();
+    let mut simulator = StabilizingSimulator::new(graph, options.run_seed());
+    simulator.inject_faults(1.0);
+    let solve_now = Instant::now();
+    let stabilize_round = simulator.stabilize(MAX_STABILIZING_ROUND);
+    let mut restabilize_rounds: Vec<Option<usize>> = vec![];
+    let mut num_corrupted = 0;
+    for _ in 0..options.num_fault {
+        if stabilize_round.is_none() {
+            break;
+        }
+        num_corrupted += simulator.inject_faults(options.fault_rate);
+        restabilize_rounds.push(simulator.stabilize(MAX_STABILIZING_ROUND));
+    }
+    let solve_time = solve_now.elapsed().as_millis();
+    let build_and_solve = now.elapsed().as_millis();
+
+    let mis = simulator.get_mis_result();
+    let verifier = Verifier::new(&mis, graph);
+    let result = verifier.verify() && stabilize_round.is_some() && restabilize_rounds.iter().all(|r| r.is_some());
+    write_outputs(graph, &verifier.mis, &verifier.find_violations(), &simulator.decide_round, &simulator.history, options)?;
+
+    let restabilized: Vec<usize> = restabilize_rounds.iter().flatten().cloned().collect();
+    let mut extra = format!("model stabilizing stabilizeRounds {:?} faults {} faultRate {} corrupted {} restabilizeRounds {:?} maxRestabilize {} avgRestabilize {:.3} messages {} ",
+        stabilize_round,
+        restabilize_rounds.len(),
+        options.fault_rate,
+        num_corrupted,
+        restabilize_rounds,
+        restabilized.iter().max().cloned().unwrap_or(0),
+        restabilized.iter().sum::<usize>() as f64 / restabilized.len().max(1) as f64,
+        simulator.num_message,
+    );
+    extra.push_str(&comparison_summary(graph, &mis, options.run_seed(), options));
+    let summary = summarize(result, simulator.round.saturating_sub(1), &mis, max_degree, (build_and_solve, solve_time), &extra, graph);
+    println!("{}", summary);
+
+    Ok((summary, result))
+}
+
+// the mis is checked after every update, changed counts the nodes that joined or left
+// the mis, affected the undecided nodes the algorithm re-ran on
+fn solve_dynamic(graph: &[Vec<usize>], weights: &[f32], max_degree: usize, num_update: usize, options: &RunOptions) -> Result<(String, bool)> {
+    let mut creater = Creater::new();
+    creater.graph = graph.to_vec();
+    let updates = creater.random_updates(num_update, max_degree);
+
+    let now = Instant::now();
+    let mut dynamic = DynamicMis::new(graph, weights, options.algorithm, options.run_seed()).map_err(invalid_input)?;
+    dynamic.set_desire(options.desire);
+    let init_round = dynamic.initialize();
+    let mut result = dynamic.verifier().verify();
+    let solve_now = Instant::now();
+    let mut changed: Vec<usize> = vec![];
+    let mut num_affected = 0;
+    let mut num_repair_round = 0;
+    for (i, update) in updates.iter().enumerate() {
+        let adjustment = dynamic.apply(update).map_err(invalid_input)?;
+        if options.per_update {
+            println!("update {} {} {}", i, update, adjustment_summary(&adjustment));
+        }
+        changed.push(adjustment.changed);
+        num_affected += adjustment.affected;
+        num_repair_round += adjustment.round;
+        if !dynamic.verifier().verify() {
+            println!("invalid mis after update {:?}", update);
+            result = false;
+        }
+    }
+    let solve_time = solve_now.elapsed().as_millis();
+    let build_and_solve = now.elapsed().as_millis();
+
+    let extra = format!("model dynamic nodes {} updates {} affected {} changed {} maxChanged {} avgChanged {:.3} initRounds {} repairRounds {} ",
+        dynamic.num_node(),
+        updates.len(),
+        num_affected,
+        changed.iter().sum::<usize>(),
+        changed.iter().max().cloned().unwrap_or(0),
+        changed.iter().sum::<usize>() as f64 / changed.len().max(1) as f64,
+        init_round,
+        num_repair_round,
+    );
+    let summary = summarize(result, init_round.saturating_sub(1), &dynamic.mis, max_degree, (build_and_solve, solve_time), &extra, graph);
+    println!("{}", summary);
+
+    Ok((summary, result))
+}
+
+fn adjustment_summary(adjustment: &Adjustment) -> String {
+    format!("affected {} changed {} rounds {}", adjustment.affected, adjustment.changed, adjustment.round)
+}
+
+fn write_outputs(
+    graph: &[Vec<usize>],
+    mis: &HashSet<usize>,
+    violations: &[Violation],
+    decide_round: &[Option<usize>],
+    history: &[RoundState],
+    options: &RunOptions,
+) -> Result<()> {
+    if let Some(path) = options.output_path(&options.dot) {
+        DotExporter::new(graph, mis, decide_round, violations).store(&path)?;
+    }
+    if let Some(dir) = options.output_path(&options.animate) {
+        let layout = match options.layout.as_ref() {
+            Some(path) => Layout::load(path, graph.len())?,
+            None => Layout::force_directed(graph, 300),
+        };
+        Animator::new(graph, history, layout).store(&dir)?;
+    }
+    Ok(())
+}
+
+fn comparison_summary(graph: &[Vec<usize>], mis: &HashSet<usize>, seed: u64, options: &RunOptions) -> String {
+    let mut extra = String::new();
+    if options.baseline {
+        extra.push_str(&baseline_summary(graph, mis, seed));
+    }
+    if options.exact {
+        extra.push_str(&exact_summary(graph, mis, options.exact_branches));
+    }
+    extra
+}
+
+fn summarize(
+    result: bool,
+    round: usize,
+    mis: &HashSet<usize>,
+    max_degree: usize,
+    times: (u128, u128), //build and solve, solve only
+    extra: &str,
+    graph: &[Vec<usize>],
+) -> String {
+    format!("{} round {} misSize {} maxDeg {} ttime {} stime {} {}sol {:?} input {:?}\n", 
         result,
         round,
         mis.len(),
         max_degree,
-        build_and_solve,
-        solve_time,
+        times.0,
+        times.1,
+        extra,
         mis,
         graph,
-    );
-    println!("{}", summary);
+    )
+}
+
+// sol lists the color of every node, -1 when uncolored
+fn summarize_coloring(verifier: &ColoringVerifier, round: usize, times: (u128, u128)) -> String {
+    let max_degree = verifier.max_degree();
+    let colors: Vec<i64> = verifier.colors.iter().map(|c| c.map_or(-1, |c| c as i64)).collect();
+    format!("{} round {} colors {} palette {} maxDeg {} ttime {} stime {} algorithm coloring sol {:?} input {:?}\n",
+        verifier.verify(),
+        round,
+        verifier.num_color(),
+        max_degree + 1,
+        max_degree,
+        times.0,
+        times.1,
+        colors,
+        verifier.graph,
+    )
+}
 
-    (summary, result)
+// sol lists the matched edges
+fn summarize_matching(verifier: &MatchingVerifier, round: usize, max_degree: usize, times: (u128, u128)) -> String {
+    let matching = verifier.get_matching();
+    format!("{} round {} matchingSize {} maxDeg {} ttime {} stime {} algorithm matching sol {:?} input {:?}\n",
+        verifier.verify(),
+        round,
+        matching.len(),
+        max_degree,
+        times.0,
+        times.1,
+        matching,
+        verifier.graph,
+    )
+}
+
+// sizes maps a component size to the number of components of that size, nothing is
+// left when the run ended before the snapshot
+fn shatter_summary(round: usize, residual_graph: &ResidualGraph) -> String {
+    format!("shatterRound {} residual {} components {} largest {} sizes {:?} ",
+        round,
+        residual_graph.nodes.len(),
+        residual_graph.components.len(),
+        residual_graph.largest_component(),
+        residual_graph.size_distribution(),
+    )
+}
+
+// ratio is the distributed mis weight over the sequential weighted greedy one
+fn weight_summary(graph: &[Vec<usize>], weights: &[f32], mis: &HashSet<usize>) -> String {
+    let greedy = Baseline::new(graph).weighted_greedy(weights);
+    let weight: f32 = mis.iter().map(|id| weights[*id]).sum();
+    let greedy_weight: f32 = greedy.iter().map(|id| weights[*id]).sum();
+    format!("weight {} greedyWeight {} weightRatio {:.3} ", weight, greedy_weight, weight / greedy_weight.max(f32::MIN_POSITIVE))
 }
 
-fn input_file_graph(filename: &str) {
+// the reference run has no byzantine node and the same seed, so both runs make the same
+// random choices until the first lie is heard
+fn byzantine_summary(
+    graph: &[Vec<usize>],
+    weights: &[f32],
+    strategies: &[Option<Strategy>],
+    outcome: (usize, &HashSet<usize>, usize), //round, mis, undecided nodes at the round limit
+    violations: &[Violation],
+    seed: u64,
+    options: &RunOptions,
+) -> String {
+    let (round, mis, undecided) = outcome;
+    let byzantine = byzantine_nodes(strategies);
+    let strategy = strategies.iter().flatten().next().map_or("none", |s| s.name());
+    let mut config = RunConfig::new(options.algorithm, seed);
+    config.desire = options.desire;
+    let reference = run_threaded(graph, weights, &config);
+    let (reference_mis, reference_round) = (reference.get_mis_result(), reference.round());
+    let num_independence = violations.iter().filter(|v| matches!(v, Violation::Independence(_, _))).count();
+    format!("byzantine {} strategy {} byzantineInMis {} undecided {} honestViolations {} independence {} maximality {} referenceRound {} referenceMisSize {} roundRatio {:.3} ",
+        byzantine.len(),
+        strategy,
+        mis.intersection(&byzantine).count(),
+        undecided,
+        violations.len(),
+        num_independence,
+        violations.len() - num_independence,
+        reference_round,
+        reference_mis.len(),
+        round as f64 / reference_round.max(1) as f64,
+    )
+}
+
+// bits are only counted by the bit-level algorithm
+fn algorithm_summary(algorithm: Algorithm, num_bit: usize) -> String {
+    match algorithm {
+        Algorithm::Luby | Algorithm::Coloring | Algorithm::Matching => String::new(),
+        Algorithm::Metivier => format!("algorithm {} bits {} ", algorithm.name(), num_bit),
+        Algorithm::Weighted => format!("algorithm {} ", algorithm.name()),
+    }
+}
+
+// only runs away from the default parameters report them
+fn desire_summary(algorithm: Algorithm, desire: &DesireConfig) -> String {
+    match algorithm {
+        Algorithm::Luby | Algorithm::Weighted if *desire != DesireConfig::default() => format!("desire {} ", desire.describe()),
+        _ => String::new(),
+    }
+}
+
+// approx is the distributed mis size over the maximum independent set size, both are
+// bounds once the search ran out of branches
+fn exact_summary(graph: &[Vec<usize>], mis: &HashSet<usize>, max_branch: usize) -> String {
+    let mut solver = ExactSolver::new(graph);
+    solver.set_max_branch(max_branch);
+    let maximum = solver.solve();
+    let (lower, upper) = if solver.is_exhausted { (">=", "<=") } else { ("", "") };
+    format!("maxIS {}{} approx {}{:.3} ", lower, maximum.len(), upper, mis.len() as f64 / maximum.len().max(1) as f64)
+}
+
+// ratio is the distributed mis size over the largest greedy one
+fn baseline_summary(graph: &[Vec<usize>], mis: &HashSet<usize>, seed: u64) -> String {
+    let baseline = Baseline::new(graph);
+    let random = baseline.greedy(GreedyOrder::Random(seed)).len();
+    let min_degree = baseline.greedy(GreedyOrder::MinDegree).len();
+    let lexicographic = baseline.greedy(GreedyOrder::Lexicographic).len();
+    let best = random.max(min_degree).max(lexicographic).max(1);
+    format!("greedyRand {} greedyMinDeg {} greedyLex {} ratio {:.3} ",
+        random,
+        min_degree,
+        lexicographic,
+        mis.len() as f64 / best as f64,
+    )
+}
+
+fn input_file_graph(filename: &str, options: &RunOptions) -> Result<()> {
     println!("input_file_graph");
-    let mut graph: Vec<Vec<usize>> = vec![];
-    let mut num_node: usize = 0;
-    let f = File::open(filename).expect("Unable to open file");
-    let f = BufReader::new(f);
-    for line in f.lines() {
-        let line = line.expect("Unable to read line");
-        num_node += 1;
-        let mut tokens: Vec<&str> = line.split(' ').collect();
-        tokens.pop();
-        let src = tokens[0];
-        let mut dsts: Vec<usize> = vec![];
-        for i in 1..tokens.len() {
-            dsts.push(tokens[i].parse::<usize>().unwrap());
-        }
-        graph.push(dsts);
-    }
-    solve_graph(&graph, num_node, 0 as usize );
+    let mut graph_creater = Creater::new();
+    let graph = graph_creater.load_graph(filename)?;
+    solve_graph(&graph, &graph_creater.weights, graph_creater.get_max_degree(), options)?;
+    Ok(())
+}
+
+fn coordinator_process(matches: &ArgMatches) -> Result<()> {
+    let mut graph_creater = Creater::new();
+    let graph = graph_creater.load_graph(matches.value_of("graph").unwrap())?;
+    let num_process = value_t!(matches, "processes", usize).unwrap_or_else(|e| e.exit());
+    let port = value_t!(matches, "port", u16).unwrap_or(7000);
+    let algorithm = parse_algorithm(matches)?;
+
+    let now = Instant::now();
+    let coordinator = network::tcp::run_coordinator(graph.len(), num_process, port)?;
+    let solve_time = now.elapsed().as_millis();
+    if algorithm == Algorithm::Coloring {
+        let verifier = ColoringVerifier::new(&coordinator.colors, &graph);
+        println!("{}", summarize_coloring(&verifier, coordinator.round, (solve_time, solve_time)));
+        return Ok(());
+    }
+    if algorithm == Algorithm::Matching {
+        let verifier = MatchingVerifier::new(&coordinator.partners, &graph);
+        println!("{}", summarize_matching(&verifier, coordinator.round, graph_creater.get_max_degree(), (solve_time, solve_time)));
+        return Ok(());
+    }
+    let mis = coordinator.get_mis_result();
+    let verifier = Verifier::new(&mis, &graph);
+    let result = verifier.verify();
+    if let Some(path) = matches.value_of("dot") {
+        DotExporter::new(&graph, &mis, &coordinator.decide_round, &verifier.find_violations()).store(path)?;
+    }
+    let mut extra = algorithm_summary(algorithm, coordinator.num_bit);
+    if algorithm == Algorithm::Weighted {
+        extra.push_str(&weight_summary(&graph, &graph_creater.weights, &mis));
+    }
+    let summary = summarize(result, coordinator.round, &mis, graph_creater.get_max_degree(), (solve_time, solve_time), &extra, &graph);
+    println!("{}", summary);
+    Ok(())
+}
+
+fn node_process(matches: &ArgMatches) -> Result<()> {
+    let mut graph_creater = Creater::new();
+    let graph = graph_creater.load_graph(matches.value_of("graph").unwrap())?;
+    let index = value_t!(matches, "index", usize).unwrap_or_else(|e| e.exit());
+    let num_process = value_t!(matches, "processes", usize).unwrap_or_else(|e| e.exit());
+    let addr = matches.value_of("addr").unwrap_or("127.0.0.1:7000");
+    network::tcp::run_node_process(&graph, &graph_creater.weights, index, num_process, addr, parse_algorithm(matches)?, parse_desire(matches)?)
+}
+
+// starts the coordinator and every node process as children of this process
+fn launch(matches: &ArgMatches) -> Result<()> {
+    let num_process = value_t!(matches, "processes", usize).unwrap_or_else(|e| e.exit());
+    let port = value_t!(matches, "port", u16).unwrap_or(7000);
+    let algorithm = parse_algorithm(matches)?;
+    let graph_path = match matches.value_of("graph") {
+        Some(path) => path.to_string(),
+        None => {
+            let num_node = value_t!(matches, "node", usize).unwrap_or_else(|e| e.exit());
+            let num_degree = value_t!(matches, "degree", usize).unwrap_or_else(|e| e.exit());
+            let mut graph_creater = Creater::new();
+            graph_creater.generate(num_node, num_degree);
+            if algorithm == Algorithm::Weighted {
+                graph_creater.random_weights(MAX_WEIGHT);
+            }
+            graph_creater.store_graph("graph".to_string())?;
+            "graph".to_string()
+        }
+    };
+
+    // every parameter is written out, so the node processes need no config file
+    let desire = parse_desire(matches)?.describe();
+    let exe = std::env::current_exe()?;
+    let mut coordinator = Command::new(&exe)
+        .args(["coordinator", "--graph", &graph_path])
+        .args(["--processes", &num_process.to_string(), "--port", &port.to_string()])
+        .args(["--algorithm", algorithm.name()])
+        .spawn()?;
+    let mut children: Vec<Child> = vec![];
+    for index in 0..num_process {
+        children.push(Command::new(&exe)
+            .args(["node", "--graph", &graph_path, "--index", &index.to_string()])
+            .args(["--processes", &num_process.to_string(), "--addr", &format!("127.0.0.1:{}", port)])
+            .args(["--algorithm", algorithm.name(), "--desire", &desire])
+            .spawn()?);
+    }
+    let status = coordinator.wait()?;
+    for mut child in children {
+        child.wait()?;
+    }
+    if !status.success() {
+        return Err(std::io::Error::other(format!("coordinator exits with {}", status)));
+    }
+    Ok(())
+}
+
+// one line per batch and per query, the mis is verified after every batch
+fn dynamic_stream(matches: &ArgMatches) -> Result<()> {
+    let algorithm = parse_algorithm(matches)?;
+    if matches!(algorithm, Algorithm::Coloring | Algorithm::Matching) {
+        return Err(invalid_input(format!("the dynamic mode repairs an mis, not a {}", algorithm.name())));
+    }
+    let mut graph_creater = Creater::new();
+    let graph = graph_creater.load_graph(matches.value_of("graph").unwrap())?;
+    let stream_path = matches.value_of("stream").unwrap();
+    if matches.is_present("generate") {
+        let num_batch = value_t!(matches, "generate", usize).unwrap_or_else(|e| e.exit());
+        let batch_size = value_t!(matches, "batch_size", usize).unwrap_or(10);
+        graph_creater.random_stream(num_batch, batch_size, graph_creater.get_max_degree()).store(stream_path)?;
+    }
+    let stream = UpdateStream::load(stream_path)?;
+    let seed = value_t!(matches, "seed", u64).unwrap_or_else(|_| thread_rng().gen());
+    let is_per_update = matches.is_present("per_update");
+
+    let mut dynamic = DynamicMis::new(&graph, &graph_creater.weights, algorithm, seed).map_err(invalid_input)?;
+    dynamic.set_desire(parse_desire(matches)?);
+    let init_round = dynamic.initialize();
+    let mut result = dynamic.verifier().verify();
+    println!("init nodes {} misSize {} rounds {} valid {}", dynamic.num_node(), dynamic.mis.len(), init_round, result);
+    let mut num_update = 0;
+    let mut changed: Vec<usize> = vec![];
+    for batch in stream.batches() {
+        let time = batch[0].time;
+        let (mut num_affected, mut num_changed, mut num_round) = (0, 0, 0);
+        for timed in batch.iter() {
+            match &timed.operation {
+                Operation::Update(update) => {
+                    let adjustment = dynamic.apply(update)
+                        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, format!("time {}: {}", time, e)))?;
+                    if is_per_update {
+                        println!("update time {} {} {}", time, update, adjustment_summary(&adjustment));
+                    }
+                    num_affected += adjustment.affected;
+                    num_changed += adjustment.changed;
+                    num_round += adjustment.round;
+                    changed.push(adjustment.changed);
+                    num_update += 1;
+                },
+                Operation::Query => {
+                    let mut mis: Vec<usize> = dynamic.mis.iter().cloned().collect();
+                    mis.sort();
+                    println!("query time {} misSize {} sol {:?}", time, mis.len(), mis);
+                },
+            }
+        }
+        let is_valid = dynamic.verifier().verify();
+        result &= is_valid;
+        println!("batch time {} operations {} affected {} changed {} rounds {} nodes {} misSize {} valid {}",
+            time, batch.len(), num_affected, num_changed, num_round, dynamic.num_node(), dynamic.mis.len(), is_valid);
+    }
+    println!("{} batches {} updates {} changed {} maxChanged {} avgChanged {:.3}",
+        result,
+        stream.batches().len(),
+        num_update,
+        changed.iter().sum::<usize>(),
+        changed.iter().max().cloned().unwrap_or(0),
+        changed.iter().sum::<usize>() as f64 / changed.len().max(1) as f64,
+    );
+    Ok(())
+}
+
+fn explore(matches: &ArgMatches) -> Result<()> {
+    let mut graph_creater = Creater::new();
+    let graph = graph_creater.load_graph(matches.value_of("graph").unwrap())?;
+    let algorithm = parse_algorithm(matches)?;
+    if matches!(algorithm, Algorithm::Coloring | Algorithm::Matching) {
+        return Err(invalid_input(format!("the explorer checks an mis, not a {}", algorithm.name())));
+    }
+    let seed = value_t!(matches, "seed", u64).unwrap_or(0);
+    let desire = parse_desire(matches)?;
+    let max_execution = value_t!(matches, "max_executions", usize).unwrap_or(100000);
+    let mut explorer = Explorer::new(&graph, &graph_creater.weights, algorithm, seed);
+    explorer.set_desire(desire);
+    let exploration = explorer.explore(max_execution);
+    println!("explore nodes {} algorithm {} seed {} {}executions {} complete {} maxDeliveries {} asleep {} merged {} stageStates {}",
+        graph.len(),
+        algorithm.name(),
+        seed,
+        desire_summary(algorithm, &desire),
+        exploration.num_execution,
+        exploration.is_complete,
+        exploration.max_depth,
+        exploration.num_redundant,
+        exploration.num_merged,
+        exploration.num_state,
+    );
+    println!("deadlocks {} panics {} wrongMis {} stepLimit {} lostMessages {} distinctMis {}",
+        exploration.num_deadlock,
+        exploration.num_panic,
+        exploration.num_wrong,
+        exploration.num_step_limit,
+        exploration.num_lost,
+        exploration.results.len(),
+    );
+    for (mis, num_execution) in exploration.results.iter() {
+        println!("mis {:?} finishedExecutions {}", mis, num_execution);
+    }
+    for counterexample in exploration.counterexamples.iter() {
+        println!("first {} after {} deliveries, last ones:", counterexample.anomaly.describe(), counterexample.schedule.len());
+        let start = counterexample.schedule.len().saturating_sub(10);
+        for (step, link) in counterexample.schedule.iter().enumerate().skip(start) {
+            println!("  {} {}", step, describe_link(link));
+        }
+    }
+    if let (Some(path), Some(counterexample)) = (matches.value_of("trace"), exploration.counterexamples.first()) {
+        let tracer = Tracer::create(path, &TraceHeader {
+            seed,
+            algorithm,
+            graph: graph.to_vec(),
+            weights: graph_creater.weights.clone(),
+            stop_round: None,
+            strategies: vec![None; graph.len()],
+            desire,
+        })?;
+        explorer.rerun(&counterexample.schedule, Some(tracer.sender.clone()));
+        tracer.finish()?;
+        println!("trace of the first {} written into {}", counterexample.anomaly.describe(), path);
+    }
+    Ok(())
+}
+
+fn describe_link(link: &Link) -> String {
+    let describe = |endpoint: &Endpoint| match endpoint {
+        Endpoint::Coordinator => "coordinator".to_string(),
+        Endpoint::Node(id) => format!("node {}", id),
+    };
+    format!("{} -> {}", describe(&link.0), describe(&link.1))
+}
+
+// the base setting of --desire is always measured, the best setting of every family is
+// compared with it
+fn sweep(matches: &ArgMatches) -> Result<()> {
+    let algorithm = parse_algorithm(matches)?;
+    if !matches!(algorithm, Algorithm::Luby | Algorithm::Weighted) {
+        return Err(invalid_input(format!("only luby and weighted have desire levels, not {}", algorithm.name())));
+    }
+    let base = parse_desire(matches)?;
+    let num_case = value_t!(matches, "cases", usize).unwrap_or(10);
+    let seed = value_t!(matches, "seed", u64).unwrap_or(0);
+    let max_round = value_t!(matches, "max_rounds", usize).unwrap_or(1000);
+    let axes: Vec<&str> = matches.values_of("vary").map_or(vec![], |values| values.collect());
+    let (mut settings, num_invalid) = grid(base, &axes).map_err(invalid_input)?;
+    if !settings.contains(&base) {
+        settings.insert(0, base);
+    }
+    let max_weight = Some(MAX_WEIGHT).filter(|_| algorithm == Algorithm::Weighted);
+
+    let mut families: Vec<Family> = vec![];
+    for family in matches.values_of("family").into_iter().flatten() {
+        let sizes: Vec<usize> = family.split(':')
+            .map(|size| size.trim().parse::<usize>())
+            .collect::<std::result::Result<_, _>>()
+            .map_err(|_| invalid_input(format!("invalid family {}", family)))?;
+        match sizes.as_slice() {
+            [num_node, num_degree] => families.push(Family::generate(*num_node, *num_degree, num_case, max_weight, seed)),
+            _ => return Err(invalid_input(format!("family {} is not nodes:degree", family))),
+        }
+    }
+    for path in matches.values_of("graph").into_iter().flatten() {
+        let mut graph_creater = Creater::new();
+        let graph = graph_creater.load_graph(path)?;
+        families.push(Family::repeat(path, &graph, &graph_creater.weights, num_case, seed));
+    }
+    if families.is_empty() {
+        return Err(invalid_input("no graph to sweep, give --family or --graph".to_string()));
+    }
+
+    println!("sweep algorithm {} families {} settings {} invalidSettings {} cases {} seed {} maxRounds {}",
+        algorithm.name(), families.len(), settings.len(), num_invalid, num_case, seed, max_round);
+    let mut overall: Vec<Measure> = vec![Measure::default(); settings.len()];
+    for family in families.iter() {
+        let mut measures: Vec<Measure> = vec![];
+        for (desire, total) in settings.iter().zip(overall.iter_mut()) {
+            let measure = measure(family, algorithm, *desire, max_round);
+            println!("family {} desire {} runs {} meanRounds {:.2} maxRounds {} unfinished {} invalid {} meanMis {:.1}",
+                family.name,
+                desire.describe(),
+                measure.num_run,
+                measure.mean_round(),
+                measure.max_round,
+                measure.num_unfinished,
+                measure.num_invalid,
+                measure.mean_mis(),
+            );
+            total.merge(&measure);
+            measures.push(measure);
+        }
+        print_best(&format!("family {}", family.name), &settings, &measures, &base);
+    }
+    if families.len() > 1 {
+        print_best("overall", &settings, &overall, &base);
+    }
+    Ok(())
+}
+
+fn print_best(scope: &str, settings: &[DesireConfig], measures: &[Measure], base: &DesireConfig) {
+    let mut best = 0;
+    for (i, measure) in measures.iter().enumerate() {
+        if measure.is_better(&measures[best]) {
+            best = i;
+        }
+    }
+    let base_measure = &measures[settings.iter().position(|desire| desire == base).unwrap()];
+    println!("best {} desire {} meanRounds {:.2} unfinished {} baseMeanRounds {:.2} baseUnfinished {}",
+        scope,
+        settings[best].describe(),
+        measures[best].mean_round(),
+        measures[best].num_unfinished + measures[best].num_invalid,
+        base_measure.mean_round(),
+        base_measure.num_unfinished + base_measure.num_invalid,
+    );
+}
+
+fn replay(matches: &ArgMatches) -> Result<()> {
+    let (header, events) = read_trace(matches.value_of("trace").unwrap())?;
+    println!("replay {} events, seed {}, {} nodes", events.len(), header.seed, header.graph.len());
+    let mut replayer = Replayer::new(&header);
+    match replayer.run(&events, matches.is_present("verbose")) {
+        Ok(num_step) => {
+            println!("no divergence after {} events, mis {:?}", num_step, replayer.coordinator.get_sorted_mis());
+        },
+        Err(divergence) => {
+            println!("divergence at event {}", divergence.step);
+            println!("  expected: {}", divergence.expected.describe());
+            println!("  found:    {}", divergence.found);
+        },
+    }
+    Ok(())
 }
@@ -0,0 +1,246 @@
+use crate::roles::node::{Node, Message, CentralMessage, Algorithm};
+use crate::roles::coordinator::{Coordinator};
+use crate::roles::graph::{undirected_neighbors};
+use crate::roles::desire::{DesireConfig};
+use serde::{Serialize, Deserialize};
+use bincode::{serialize, deserialize};
+
+use std::net::{TcpListener, TcpStream, Shutdown};
+use std::io::{Read, Write, Result, Error, ErrorKind};
+use std::sync::mpsc::{Sender, Receiver, channel};
+use std::sync::{Arc, Mutex};
+use std::collections::{HashMap};
+use std::thread;
+use std::time::Duration;
+
+// every frame on the wire is a little endian u32 length followed by the bincode payload
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Frame {
+    Hello(Vec<usize>), //node ids hosted by the process
+    Peer((usize, Message)), //destination node id, message
+    Central(CentralMessage),
+}
+
+pub fn write_frame(stream: &mut TcpStream, frame: &Frame) -> Result<()> {
+    let payload = serialize(frame).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+    stream.write_all(&(payload.len() as u32).to_le_bytes())?;
+    stream.write_all(&payload)?;
+    stream.flush()
+}
+
+pub fn read_frame(stream: &mut TcpStream) -> Result<Frame> {
+    let mut len_bytes = [0u8; 4];
+    stream.read_exact(&mut len_bytes)?;
+    let mut payload = vec![0u8; u32::from_le_bytes(len_bytes) as usize];
+    stream.read_exact(&mut payload)?;
+    deserialize(&payload).map_err(|e| Error::new(ErrorKind::InvalidData, e))
+}
+
+// node ids are dealt round robin among the node processes
+pub fn hosted_nodes(num_node: usize, index: usize, num_process: usize) -> Vec<usize> {
+    (0..num_node).filter(|id| id % num_process == index).collect()
+}
+
+// a single thread owns the write half so frames from different sources never interleave
+fn start_writer(mut stream: TcpStream) -> Sender<Frame> {
+    let (frame_sender, frame_receiver): (Sender<Frame>, Receiver<Frame>) = channel();
+    thread::spawn(move || {
+        for frame in frame_receiver.iter() {
+            if write_frame(&mut stream, &frame).is_err() {
+                break;
+            }
+        }
+    });
+    frame_sender
+}
+
+// returns a sender that looks like a local node, but wraps every message into a frame
+fn start_forwarder<T, F>(frame_sender: Sender<Frame>, wrap: F) -> Sender<T>
+where
+    T: Send + 'static,
+    F: Fn(T) -> Frame + Send + 'static,
+{
+    let (sender, receiver): (Sender<T>, Receiver<T>) = channel();
+    thread::spawn(move || {
+        for message in receiver.iter() {
+            if frame_sender.send(wrap(message)).is_err() {
+                break;
+            }
+        }
+    });
+    sender
+}
+
+fn connect_retry(addr: &str) -> Result<TcpStream> {
+    let mut attempt = 0;
+    loop {
+        match TcpStream::connect(addr) {
+            Ok(stream) => return Ok(stream),
+            Err(e) => {
+                attempt += 1;
+                if attempt == 50 {
+                    return Err(e);
+                }
+                thread::sleep(Duration::from_millis(100));
+            }
+        }
+    }
+}
+
+// hosts the nodes of one process, every message to a node outside the process
+// and every message to the coordinator goes through the coordinator connection
+pub fn run_node_process(graph: &[Vec<usize>], weights: &[f32], index: usize, num_process: usize, addr: &str, algorithm: Algorithm, desire: DesireConfig) -> Result<()> {
+    let neighbors = undirected_neighbors(graph);
+    let hosted = hosted_nodes(graph.len(), index, num_process);
+    let mut stream = connect_retry(addr)?;
+    stream.set_nodelay(true)?;
+    let frame_sender = start_writer(stream.try_clone()?);
+    frame_sender.send(Frame::Hello(hosted.clone())).expect("unable to send hello");
+
+    let central_sender = start_forwarder(frame_sender.clone(), Frame::Central);
+    let mut node_list: HashMap<usize, Node> = HashMap::new();
+    let mut sender_list: HashMap<usize, Sender<Message>> = HashMap::new();
+    for id in hosted.iter() {
+        let (mut node, sender_to_node) = Node::new(*id, central_sender.clone());
+        node.set_algorithm(algorithm);
+        node.set_desire(desire);
+        node.set_weight(weights[*id]);
+        node_list.insert(*id, node);
+        sender_list.insert(*id, sender_to_node);
+    }
+    drop(central_sender);
+
+    let mut remote_list: HashMap<usize, Sender<Message>> = HashMap::new();
+    for id in hosted.iter() {
+        for n_id in neighbors[*id].iter() {
+            let sender = match sender_list.get(n_id) {
+                Some(sender) => sender.clone(),
+                None => remote_list.entry(*n_id).or_insert_with(|| {
+                    let remote_id = *n_id;
+                    start_forwarder(frame_sender.clone(), move |m| Frame::Peer((remote_id, m)))
+                }).clone(),
+            };
+            node_list.get_mut(id).unwrap().register_neighbor(*n_id, sender);
+        }
+    }
+    drop(remote_list);
+    drop(frame_sender);
+
+    let mut join_handlers = vec![];
+    for (_, node) in node_list.drain() {
+        join_handlers.push(node.start());
+    }
+
+    // deliver until the coordinator closes the connection
+    loop {
+        match read_frame(&mut stream) {
+            Ok(Frame::Peer((to, message))) => {
+                if let Some(sender) = sender_list.get(&to) {
+                    let _ = sender.send(message);
+                }
+            },
+            Ok(frame) => {
+                return Err(Error::new(ErrorKind::InvalidData, format!("unexpected frame {:?}", frame)));
+            },
+            Err(_) => break,
+        }
+    }
+    Ok(())
+}
+
+// accepts every node process, then runs the coordinator, relaying messages between
+// nodes living in different processes. a frame to a node that does not exist closes
+// every connection, so the run ends with an error instead of waiting forever
+pub fn run_coordinator(num_node: usize, num_process: usize, port: u16) -> Result<Coordinator> {
+    let listener = TcpListener::bind(("127.0.0.1", port))?;
+    let mut owner: Vec<Option<usize>> = vec![None; num_node];
+    let mut streams: Vec<TcpStream> = vec![];
+    for index in 0..num_process {
+        let (mut stream, _) = listener.accept()?;
+        stream.set_nodelay(true)?;
+        match read_frame(&mut stream)? {
+            Frame::Hello(ids) => {
+                for id in ids {
+                    match owner.get(id) {
+                        Some(None) => owner[id] = Some(index),
+                        Some(Some(_)) => return Err(Error::new(ErrorKind::InvalidData, format!("node {} is hosted twice", id))),
+                        None => return Err(Error::new(ErrorKind::InvalidData, format!("process {} hosts node {} of a graph of {} nodes", index, id, num_node))),
+                    }
+                }
+            },
+            frame => return Err(Error::new(ErrorKind::InvalidData, format!("expect hello, get {:?}", frame))),
+        }
+        streams.push(stream);
+    }
+    if let Some(id) = owner.iter().position(|o| o.is_none()) {
+        return Err(Error::new(ErrorKind::InvalidData, format!("node {} is not hosted by any process", id)));
+    }
+    let owner: Vec<usize> = owner.into_iter().map(|o| o.unwrap()).collect();
+
+    let mut process_senders: Vec<Sender<Frame>> = vec![];
+    for stream in streams.iter() {
+        process_senders.push(start_writer(stream.try_clone()?));
+    }
+
+    let mut connections: Vec<TcpStream> = vec![];
+    for stream in streams.iter() {
+        connections.push(stream.try_clone()?);
+    }
+    let connections = Arc::new(connections);
+    let relay_error: Arc<Mutex<Option<Error>>> = Arc::new(Mutex::new(None));
+    let (central_sender, central_receiver) = channel();
+    for mut stream in streams.into_iter() {
+        let central_sender = central_sender.clone();
+        let process_senders = process_senders.clone();
+        let owner = owner.clone();
+        let connections = connections.clone();
+        let relay_error = relay_error.clone();
+        thread::spawn(move || {
+            loop {
+                match read_frame(&mut stream) {
+                    Ok(Frame::Central(central_message)) => {
+                        if central_sender.send(central_message).is_err() {
+                            break;
+                        }
+                    },
+                    Ok(Frame::Peer((to, message))) => match owner.get(to) {
+                        Some(index) => {
+                            let _ = process_senders[*index].send(Frame::Peer((to, message)));
+                        },
+                        None => {
+                            *relay_error.lock().unwrap() = Some(Error::new(ErrorKind::InvalidData, format!("frame to node {} of a graph of {} nodes", to, owner.len())));
+                            for connection in connections.iter() {
+                                let _ = connection.shutdown(Shutdown::Both);
+                            }
+                            break;
+                        },
+                    },
+                    Ok(Frame::Hello(_)) => (),
+                    Err(_) => break,
+                }
+            }
+        });
+    }
+    drop(central_sender);
+
+    let mut sender_list: Vec<Sender<Message>> = vec![];
+    for id in 0..num_node {
+        sender_list.push(start_forwarder(process_senders[owner[id]].clone(), move |m| Frame::Peer((id, m))));
+    }
+    drop(process_senders);
+
+    let mut coordinator = Coordinator::new(sender_list, central_receiver);
+    coordinator.start();
+    // the relays stop reading, the connections close once the frames still queued for
+    // the node processes are written and the coordinator is dropped
+    for connection in connections.iter() {
+        let _ = connection.shutdown(Shutdown::Read);
+    }
+    if let Some(e) = relay_error.lock().unwrap().take() {
+        return Err(e);
+    }
+    if coordinator.is_disconnected {
+        return Err(Error::new(ErrorKind::ConnectionAborted, "every node process left before the end of the run"));
+    }
+    Ok(coordinator)
+}
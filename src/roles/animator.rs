@@ -0,0 +1,234 @@
+use super::coordinator::{RoundState};
+use super::graph::{undirected_edges};
+use rand::{Rng, SeedableRng};
+use rand::prng::XorShiftRng;
+
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, BufWriter, Write, Result, Error, ErrorKind};
+
+const WIDTH: f64 = 800.0;
+const HEIGHT: f64 = 800.0;
+const MARGIN: f64 = 40.0;
+const RADIUS: f64 = 12.0;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum NodeStatus {
+    Active,
+    Marked,
+    Joined, //joins the mis in this round
+    Removed, //leaves in this round
+    InMis, //joined in an earlier round
+    Gone, //left in an earlier round
+}
+
+impl NodeStatus {
+    fn style(&self) -> (&'static str, &'static str, f64) {
+        match self {
+            NodeStatus::Active => ("#ffffff", "#333333", 1.5),
+            NodeStatus::Marked => ("#f5a623", "#333333", 1.5),
+            NodeStatus::Joined => ("#2f6fd6", "#000000", 3.5),
+            NodeStatus::Removed => ("#b0b0b0", "#000000", 3.5),
+            NodeStatus::InMis => ("#9dbbe8", "#9dbbe8", 1.0),
+            NodeStatus::Gone => ("#f0f0f0", "#dddddd", 1.0),
+        }
+    }
+}
+
+// node positions, scaled into the drawing area
+pub struct Layout {
+    pub positions: Vec<(f64, f64)>,
+}
+
+impl Layout {
+    // Fruchterman-Reingold with a fixed seed, so the same graph is always drawn the same way
+    pub fn force_directed(graph: &[Vec<usize>], iterations: usize) -> Layout {
+        let num_node = graph.len();
+        let mut rng = XorShiftRng::seed_from_u64(0);
+        let mut positions: Vec<(f64, f64)> = (0..num_node)
+            .map(|_| (rng.gen_range(0.0, WIDTH), rng.gen_range(0.0, HEIGHT)))
+            .collect();
+        if num_node < 2 {
+            return Layout::scaled(positions);
+        }
+        let edges = undirected_edges(graph);
+        let k = (WIDTH * HEIGHT / num_node as f64).sqrt();
+        let mut temperature = WIDTH / 10.0;
+        for _ in 0..iterations {
+            let mut shift: Vec<(f64, f64)> = vec![(0.0, 0.0); num_node];
+            for i in 0..num_node {
+                for j in (i + 1)..num_node {
+                    let (dx, dy) = (positions[i].0 - positions[j].0, positions[i].1 - positions[j].1);
+                    let distance = (dx * dx + dy * dy).sqrt().max(0.01);
+                    let force = k * k / distance;
+                    shift[i].0 += dx / distance * force;
+                    shift[i].1 += dy / distance * force;
+                    shift[j].0 -= dx / distance * force;
+                    shift[j].1 -= dy / distance * force;
+                }
+            }
+            for (i, j) in edges.iter() {
+                let (dx, dy) = (positions[*i].0 - positions[*j].0, positions[*i].1 - positions[*j].1);
+                let distance = (dx * dx + dy * dy).sqrt().max(0.01);
+                let force = distance * distance / k;
+                shift[*i].0 -= dx / distance * force;
+                shift[*i].1 -= dy / distance * force;
+                shift[*j].0 += dx / distance * force;
+                shift[*j].1 += dy / distance * force;
+            }
+            for i in 0..num_node {
+                let length = (shift[i].0 * shift[i].0 + shift[i].1 * shift[i].1).sqrt().max(0.01);
+                positions[i].0 += shift[i].0 / length * length.min(temperature);
+                positions[i].1 += shift[i].1 / length * length.min(temperature);
+            }
+            temperature *= 0.95;
+        }
+        Layout::scaled(positions)
+    }
+
+    // one "id x y" line per node
+    pub fn load(filename: &str, num_node: usize) -> Result<Layout> {
+        let mut positions: Vec<Option<(f64, f64)>> = vec![None; num_node];
+        let f = BufReader::new(File::open(filename)?);
+        for line in f.lines() {
+            let line = line?;
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            if tokens.is_empty() {
+                continue;
+            }
+            let invalid = || Error::new(ErrorKind::InvalidData, format!("invalid layout line {}", line));
+            if tokens.len() != 3 {
+                return Err(invalid());
+            }
+            let id = tokens[0].parse::<usize>().map_err(|_| invalid())?;
+            let x = tokens[1].parse::<f64>().map_err(|_| invalid())?;
+            let y = tokens[2].parse::<f64>().map_err(|_| invalid())?;
+            if id >= num_node {
+                return Err(invalid());
+            }
+            positions[id] = Some((x, y));
+        }
+        let mut placed: Vec<(f64, f64)> = vec![];
+        for (id, position) in positions.into_iter().enumerate() {
+            match position {
+                Some(position) => placed.push(position),
+                None => return Err(Error::new(ErrorKind::InvalidData, format!("no position for node {}", id))),
+            }
+        }
+        Ok(Layout::scaled(placed))
+    }
+
+    fn scaled(positions: Vec<(f64, f64)>) -> Layout {
+        let min_x = positions.iter().map(|p| p.0).fold(f64::INFINITY, f64::min);
+        let max_x = positions.iter().map(|p| p.0).fold(f64::NEG_INFINITY, f64::max);
+        let min_y = positions.iter().map(|p| p.1).fold(f64::INFINITY, f64::min);
+        let max_y = positions.iter().map(|p| p.1).fold(f64::NEG_INFINITY, f64::max);
+        let span_x = (max_x - min_x).max(1e-9);
+        let span_y = (max_y - min_y).max(1e-9);
+        let positions = positions.into_iter().map(|(x, y)| {
+            (MARGIN + (x - min_x) / span_x * (WIDTH - 2.0 * MARGIN),
+             MARGIN + (y - min_y) / span_y * (HEIGHT - 2.0 * MARGIN))
+        }).collect();
+        Layout { positions }
+    }
+}
+
+// renders one svg frame per round from the coordinator history and an html page
+// with a slider to step through them
+pub struct Animator {
+    pub graph: Vec<Vec<usize>>,
+    pub history: Vec<RoundState>,
+    pub layout: Layout,
+}
+
+impl Animator {
+    pub fn new(graph: &[Vec<usize>], history: &[RoundState], layout: Layout) -> Animator {
+        Animator {
+            graph: graph.to_vec(),
+            history: history.to_vec(),
+            layout,
+        }
+    }
+
+    fn statuses(&self, frame: usize) -> Vec<NodeStatus> {
+        let mut statuses = vec![NodeStatus::Active; self.graph.len()];
+        for (index, state) in self.history.iter().enumerate().take(frame + 1) {
+            let is_current = index == frame;
+            if is_current {
+                for id in state.marked.iter() {
+                    statuses[*id] = NodeStatus::Marked;
+                }
+            }
+            for id in state.joined.iter() {
+                statuses[*id] = if is_current { NodeStatus::Joined } else { NodeStatus::InMis };
+            }
+            for id in state.removed.iter() {
+                statuses[*id] = if is_current { NodeStatus::Removed } else { NodeStatus::Gone };
+            }
+        }
+        statuses
+    }
+
+    pub fn render_frame(&self, frame: usize) -> String {
+        let statuses = self.statuses(frame);
+        let state = &self.history[frame];
+        let num_active = statuses.iter().filter(|s| **s != NodeStatus::InMis && **s != NodeStatus::Gone).count();
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\">\n",
+            WIDTH, HEIGHT + MARGIN, WIDTH, HEIGHT + MARGIN);
+        svg.push_str(&format!(
+            "<text x=\"10\" y=\"20\" font-family=\"sans-serif\" font-size=\"14\">round {}: active {}, marked {}, joined {}, removed {}</text>\n",
+            state.round, num_active, state.marked.len(), state.joined.len(), state.removed.len()));
+        svg.push_str(&format!("<g transform=\"translate(0 {})\">\n", MARGIN / 2.0));
+
+        for (i, j) in undirected_edges(&self.graph) {
+            let is_residual = |s: NodeStatus| s != NodeStatus::InMis && s != NodeStatus::Gone;
+            let color = if is_residual(statuses[i]) && is_residual(statuses[j]) { "#555555" } else { "#e8e8e8" };
+            let (x1, y1) = self.layout.positions[i];
+            let (x2, y2) = self.layout.positions[j];
+            svg.push_str(&format!(
+                "<line x1=\"{:.1}\" y1=\"{:.1}\" x2=\"{:.1}\" y2=\"{:.1}\" stroke=\"{}\" stroke-width=\"1.5\"/>\n",
+                x1, y1, x2, y2, color));
+        }
+        for (i, status) in statuses.iter().enumerate() {
+            let (fill, stroke, width) = status.style();
+            let (x, y) = self.layout.positions[i];
+            svg.push_str(&format!(
+                "<circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"{}\" fill=\"{}\" stroke=\"{}\" stroke-width=\"{}\"><title>{} {:?}</title></circle>\n",
+                x, y, RADIUS, fill, stroke, width, i, status));
+            svg.push_str(&format!(
+                "<text x=\"{:.1}\" y=\"{:.1}\" font-family=\"sans-serif\" font-size=\"10\" text-anchor=\"middle\">{}</text>\n",
+                x, y + 3.5, i));
+        }
+        svg.push_str("</g>\n</svg>\n");
+        svg
+    }
+
+    // writes round_<r>.svg for every round and an index.html embedding all of them
+    pub fn store(&self, dir: &str) -> Result<()> {
+        fs::create_dir_all(dir)?;
+        let mut frames: Vec<String> = vec![];
+        for frame in 0..self.history.len() {
+            let svg = self.render_frame(frame);
+            let mut f = BufWriter::new(File::create(format!("{}/round_{}.svg", dir, frame))?);
+            f.write_all(svg.as_bytes())?;
+            f.flush()?;
+            frames.push(svg);
+        }
+
+        let mut html = String::from("<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"><title>MIS rounds</title></head>\n<body>\n");
+        html.push_str(&format!(
+            "<input id=\"slider\" type=\"range\" min=\"0\" max=\"{}\" value=\"0\" style=\"width:800px\" oninput=\"show(this.value)\">\n<span id=\"label\">round 0</span>\n",
+            self.history.len().saturating_sub(1)));
+        html.push_str("<p>white active, orange marked, blue joins the mis, grey leaves; faded nodes decided earlier</p>\n");
+        for (frame, svg) in frames.iter().enumerate() {
+            let display = if frame == 0 { "block" } else { "none" };
+            html.push_str(&format!("<div class=\"frame\" id=\"frame{}\" style=\"display:{}\">\n{}</div>\n", frame, display, svg));
+        }
+        html.push_str("<script>\nfunction show(r) {\n  var frames = document.getElementsByClassName(\"frame\");\n");
+        html.push_str("  for (var i = 0; i < frames.length; i++) { frames[i].style.display = (i == r) ? \"block\" : \"none\"; }\n");
+        html.push_str("  document.getElementById(\"label\").textContent = \"round \" + r;\n}\n</script>\n</body>\n</html>\n");
+        let mut f = BufWriter::new(File::create(format!("{}/index.html", dir))?);
+        f.write_all(html.as_bytes())?;
+        f.flush()
+    }
+}
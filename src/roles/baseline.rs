@@ -0,0 +1,144 @@
+use super::graph::{undirected_neighbors};
+use rand::{Rng, SeedableRng};
+use rand::prng::XorShiftRng;
+use std::collections::{BTreeSet, HashSet};
+
+// sequential reference solutions to judge the size of the distributed mis
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum GreedyOrder {
+    Random(u64), //seed of the random permutation
+    MinDegree, //always pick a node of minimum degree in the remaining graph
+    Lexicographic, //by increasing node id
+}
+
+pub struct Baseline {
+    pub neighbors: Vec<HashSet<usize>>,
+}
+
+impl Baseline {
+    pub fn new(graph: &[Vec<usize>]) -> Baseline {
+        let neighbors: Vec<HashSet<usize>> = undirected_neighbors(graph).into_iter().map(|n| n.into_iter().collect()).collect();
+        Baseline { neighbors }
+    }
+
+    pub fn greedy(&self, order: GreedyOrder) -> HashSet<usize> {
+        match order {
+            GreedyOrder::Random(seed) => {
+                let mut rng = XorShiftRng::seed_from_u64(seed);
+                let mut ids: Vec<usize> = (0..self.neighbors.len()).collect();
+                rng.shuffle(&mut ids);
+                self.greedy_in_order(&ids)
+            },
+            GreedyOrder::Lexicographic => {
+                let ids: Vec<usize> = (0..self.neighbors.len()).collect();
+                self.greedy_in_order(&ids)
+            },
+            GreedyOrder::MinDegree => self.greedy_min_degree(),
+        }
+    }
+
+    // sequential completion of a partial mis: the undecided nodes join by increasing id
+    // unless a neighbor is in the mis already, returns the nodes that joined
+    pub fn complete(&self, mis: &HashSet<usize>, undecided: &[usize]) -> HashSet<usize> {
+        let mut ids: Vec<usize> = undecided.to_vec();
+        ids.sort();
+        let mut taken: HashSet<usize> = mis.clone();
+        let mut added: HashSet<usize> = HashSet::new();
+        for id in ids {
+            if !taken.contains(&id) && self.neighbors[id].iter().all(|n_id| !taken.contains(n_id)) {
+                taken.insert(id);
+                added.insert(id);
+            }
+        }
+        added
+    }
+
+    // take every node whose neighbors are not taken yet
+    fn greedy_in_order(&self, ids: &[usize]) -> HashSet<usize> {
+        let mut mis: HashSet<usize> = HashSet::new();
+        let mut is_covered = vec![false; self.neighbors.len()];
+        for id in ids.iter() {
+            if is_covered[*id] {
+                continue;
+            }
+            mis.insert(*id);
+            is_covered[*id] = true;
+            for n_id in self.neighbors[*id].iter() {
+                is_covered[*n_id] = true;
+            }
+        }
+        mis
+    }
+
+    // GWMIN: take the node with the largest weight over remaining degree + 1, remove it
+    // with its neighbors, repeat, ties go to the smaller id
+    pub fn weighted_greedy(&self, weights: &[f32]) -> HashSet<usize> {
+        let num_node = self.neighbors.len();
+        let mut degree: Vec<usize> = self.neighbors.iter().map(|n| n.len()).collect();
+        let mut is_removed = vec![false; num_node];
+        let mut mis: HashSet<usize> = HashSet::new();
+        loop {
+            let mut best: Option<(f32, usize)> = None;
+            for id in (0..num_node).filter(|id| !is_removed[*id]) {
+                let score = weights[id] / (degree[id] + 1) as f32;
+                if best.is_none_or(|(best_score, _)| score > best_score) {
+                    best = Some((score, id));
+                }
+            }
+            let id = match best {
+                Some((_, id)) => id,
+                None => break,
+            };
+            mis.insert(id);
+            is_removed[id] = true;
+            for n_id in self.neighbors[id].iter() {
+                if is_removed[*n_id] {
+                    continue;
+                }
+                is_removed[*n_id] = true;
+                for m_id in self.neighbors[*n_id].iter() {
+                    if !is_removed[*m_id] {
+                        degree[*m_id] -= 1;
+                    }
+                }
+            }
+        }
+        mis
+    }
+
+    // take a node of minimum remaining degree, remove it with its neighbors, repeat
+    fn greedy_min_degree(&self) -> HashSet<usize> {
+        let num_node = self.neighbors.len();
+        let mut degree: Vec<usize> = self.neighbors.iter().map(|n| n.len()).collect();
+        let mut is_removed = vec![false; num_node];
+        let mut queue: BTreeSet<(usize, usize)> = (0..num_node).map(|i| (degree[i], i)).collect();
+        let mut mis: HashSet<usize> = HashSet::new();
+        while let Some((_, id)) = queue.iter().next().cloned() {
+            queue.remove(&(degree[id], id));
+            mis.insert(id);
+            is_removed[id] = true;
+            let mut affected: Vec<usize> = vec![];
+            for n_id in self.neighbors[id].iter() {
+                if is_removed[*n_id] {
+                    continue;
+                }
+                queue.remove(&(degree[*n_id], *n_id));
+                is_removed[*n_id] = true;
+                for m_id in self.neighbors[*n_id].iter() {
+                    if !is_removed[*m_id] {
+                        affected.push(*m_id);
+                    }
+                }
+            }
+            for m_id in affected {
+                if is_removed[m_id] {
+                    continue;
+                }
+                queue.remove(&(degree[m_id], m_id));
+                degree[m_id] -= 1;
+                queue.insert((degree[m_id], m_id));
+            }
+        }
+        mis
+    }
+}
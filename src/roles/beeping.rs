@@ -0,0 +1,165 @@
+use super::coordinator::{RoundState};
+use super::graph::{undirected_neighbors};
+use super::node::{node_seed};
+use rand::{Rng, SeedableRng};
+use rand::prng::XorShiftRng;
+use std::collections::{HashSet};
+
+// slot synchronous medium: in every slot a node either beeps or listens, and a node only
+// learns whether at least one of its active neighbors beeped, a beeping node hears its
+// neighbors as well (collision detection)
+pub struct BeepingChannel {
+    pub neighbors: Vec<Vec<usize>>,
+    pub num_slot: usize,
+    pub num_beep: usize,
+}
+
+impl BeepingChannel {
+    pub fn new(graph: &[Vec<usize>]) -> BeepingChannel {
+        let neighbors = undirected_neighbors(graph);
+        BeepingChannel {
+            neighbors,
+            num_slot: 0,
+            num_beep: 0,
+        }
+    }
+
+    // whether each node hears a beep in this slot, nodes that are not active neither beep nor hear
+    pub fn slot(&mut self, beeps: &[bool], active: &[bool]) -> Vec<bool> {
+        self.num_slot += 1;
+        self.num_beep += beeps.iter().zip(active.iter()).filter(|(b, a)| **b && **a).count();
+        (0..self.neighbors.len()).map(|id| {
+            active[id] && self.neighbors[id].iter().any(|n_id| active[*n_id] && beeps[*n_id])
+        }).collect()
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum BeepState {
+    Active,
+    InMis,
+    Covered, //a neighbor is in the mis
+}
+
+// Jeavons et al.: a node beeps with probability p in the first slot and joins the mis
+// when it beeps alone, hearing a beep halves p and silence doubles it up to 1/2; mis
+// nodes beep in the second slot so that their neighbors stop
+pub struct BeepingNode {
+    pub id: usize,
+    pub probability: f64,
+    pub state: BeepState,
+    pub is_beeping: bool,
+    pub rng: XorShiftRng,
+}
+
+impl BeepingNode {
+    pub fn new(id: usize, seed: u64) -> BeepingNode {
+        BeepingNode {
+            id,
+            probability: 0.5,
+            state: BeepState::Active,
+            is_beeping: false,
+            rng: XorShiftRng::seed_from_u64(node_seed(seed, id)),
+        }
+    }
+
+    pub fn first_slot(&mut self) -> bool {
+        self.is_beeping = self.rng.gen_range(0.0, 1.0) < self.probability;
+        self.is_beeping
+    }
+
+    // returns true when the node joins the mis
+    pub fn after_first_slot(&mut self, is_heard: bool) -> bool {
+        if self.is_beeping && !is_heard {
+            self.state = BeepState::InMis;
+            return true;
+        }
+        if is_heard {
+            self.probability /= 2.0;
+        } else {
+            self.probability = (2.0 * self.probability).min(0.5);
+        }
+        false
+    }
+
+    // returns true when the node leaves next to a mis node
+    pub fn after_second_slot(&mut self, is_heard: bool) -> bool {
+        if self.state == BeepState::Active && is_heard {
+            self.state = BeepState::Covered;
+            return true;
+        }
+        false
+    }
+}
+
+// runs the beeping nodes phase by phase, two slots per phase, until every node decided
+pub struct BeepingSimulator {
+    pub channel: BeepingChannel,
+    pub nodes: Vec<BeepingNode>,
+    pub phase: usize,
+    pub decide_round: Vec<Option<usize>>,
+    pub history: Vec<RoundState>,
+}
+
+impl BeepingSimulator {
+    pub fn new(graph: &[Vec<usize>], seed: u64) -> BeepingSimulator {
+        BeepingSimulator {
+            channel: BeepingChannel::new(graph),
+            nodes: (0..graph.len()).map(|id| BeepingNode::new(id, seed)).collect(),
+            phase: 0,
+            decide_round: vec![None; graph.len()],
+            history: vec![],
+        }
+    }
+
+    fn active(&self) -> Vec<bool> {
+        self.nodes.iter().map(|n| n.state == BeepState::Active).collect()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.nodes.iter().all(|n| n.state != BeepState::Active)
+    }
+
+    pub fn step(&mut self) {
+        let mut state = RoundState::new(self.phase);
+        let active = self.active();
+        let beeps: Vec<bool> = self.nodes.iter_mut()
+            .map(|n| n.state == BeepState::Active && n.first_slot())
+            .collect();
+        let heard = self.channel.slot(&beeps, &active);
+        for node in self.nodes.iter_mut().filter(|n| n.state == BeepState::Active) {
+            if beeps[node.id] {
+                state.marked.push(node.id);
+            }
+            if node.after_first_slot(heard[node.id]) {
+                state.joined.push(node.id);
+                self.decide_round[node.id] = Some(self.phase);
+            }
+        }
+
+        // nodes that just joined beep, the others listen
+        let mut beeps = vec![false; self.nodes.len()];
+        for id in state.joined.iter() {
+            beeps[*id] = true;
+        }
+        let heard = self.channel.slot(&beeps, &active);
+        for node in self.nodes.iter_mut() {
+            if node.after_second_slot(heard[node.id]) {
+                state.removed.push(node.id);
+                self.decide_round[node.id] = Some(self.phase);
+            }
+        }
+        self.history.push(state);
+        self.phase += 1;
+    }
+
+    pub fn run(&mut self) {
+        while !self.is_finished() {
+            self.step();
+        }
+    }
+
+    pub fn get_mis_result(&self) -> HashSet<usize> {
+        self.nodes.iter().filter(|n| n.state == BeepState::InMis).map(|n| n.id).collect()
+    }
+}
@@ -0,0 +1,51 @@
+use rand::{Rng, SeedableRng};
+use rand::prng::XorShiftRng;
+use serde::{Serialize, Deserialize};
+use std::collections::{HashSet};
+
+// a lied desire level, enough alone to make the effective degree of a neighbor reach 2
+pub const FALSE_DESIRE_LEVEL: f32 = 2.0;
+
+// how a byzantine node lies to its neighbors, otherwise it follows the protocol and
+// reports its true state to the coordinator
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Strategy {
+    AlwaysMarked, //every response claims the node is marked, so no neighbor joins next to it
+    FalseDesire, //every response claims a desire level of 2, so neighbors keep halving theirs
+    FakeJoin, //claims to have joined the mis in every round, so its neighbors leave
+}
+
+impl Strategy {
+    pub fn parse(strategy: &str) -> Option<Strategy> {
+        match strategy.to_lowercase().as_str() {
+            "marked" => Some(Strategy::AlwaysMarked),
+            "desire" => Some(Strategy::FalseDesire),
+            "joined" => Some(Strategy::FakeJoin),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Strategy::AlwaysMarked => "marked",
+            Strategy::FalseDesire => "desire",
+            Strategy::FakeJoin => "joined",
+        }
+    }
+}
+
+// the given number of distinct nodes, picked with the seed, all lie the same way
+pub fn assign_strategies(num_node: usize, num_byzantine: usize, strategy: Strategy, seed: u64) -> Vec<Option<Strategy>> {
+    let mut rng = XorShiftRng::seed_from_u64(seed ^ 0xB12A_471E_5EED_0044);
+    let mut ids: Vec<usize> = (0..num_node).collect();
+    rng.shuffle(&mut ids);
+    let mut strategies: Vec<Option<Strategy>> = vec![None; num_node];
+    for id in ids.into_iter().take(num_byzantine) {
+        strategies[id] = Some(strategy);
+    }
+    strategies
+}
+
+pub fn byzantine_nodes(strategies: &[Option<Strategy>]) -> HashSet<usize> {
+    strategies.iter().enumerate().filter(|(_, s)| s.is_some()).map(|(id, _)| id).collect()
+}
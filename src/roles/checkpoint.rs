@@ -0,0 +1,67 @@
+use super::node::{Algorithm, NodeResult};
+use super::coordinator::{RoundState};
+use super::desire::{DesireConfig};
+use serde::{Serialize, Deserialize};
+use bincode::{serialize_into, deserialize_from};
+
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Write, Result, Error, ErrorKind};
+
+// what an undecided node carries from one round to the next
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct NodeState {
+    pub id: usize,
+    pub desire_level: f32,
+    pub neighbors: Vec<(usize, f32)>, //id, weight learned from its responses
+    pub rng: Vec<u8>, //the serialized XorShiftRng, which is neither Debug nor PartialEq
+}
+
+// the simulation between two rounds, with what is needed to rebuild the nodes: decided
+// nodes have left, the others wait for the next Start
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub seed: u64,
+    pub algorithm: Algorithm,
+    pub desire: DesireConfig,
+    pub graph: Vec<Vec<usize>>,
+    pub weights: Vec<f32>,
+    pub round: usize, //next round to run
+    pub results: Vec<NodeResult>,
+    pub decide_round: Vec<Option<usize>>,
+    pub history: Vec<RoundState>,
+    pub num_bit: usize,
+    pub nodes: Vec<NodeState>, //undecided nodes, by id
+}
+
+impl Checkpoint {
+    pub fn new(seed: u64, algorithm: Algorithm, desire: DesireConfig, graph: &[Vec<usize>], weights: &[f32]) -> Checkpoint {
+        Checkpoint {
+            seed,
+            algorithm,
+            desire,
+            graph: graph.to_vec(),
+            weights: weights.to_vec(),
+            round: 0,
+            results: vec![],
+            decide_round: vec![None; graph.len()],
+            history: vec![],
+            num_bit: 0,
+            nodes: vec![],
+        }
+    }
+
+    // written aside then renamed, so a crash while writing keeps the last checkpoint
+    pub fn store(&self, filename: &str) -> Result<()> {
+        let partial = format!("{}.partial", filename);
+        let mut f = BufWriter::new(File::create(&partial)?);
+        serialize_into(&mut f, self).map_err(Error::other)?;
+        f.flush()?;
+        drop(f);
+        fs::rename(&partial, filename)
+    }
+
+    pub fn load(filename: &str) -> Result<Checkpoint> {
+        let mut f = BufReader::new(File::open(filename)?);
+        deserialize_from(&mut f).map_err(|e| Error::new(ErrorKind::InvalidData, e))
+    }
+}
@@ -1,18 +1,68 @@
 use super::node;
-use node::{Node, Message, NodeResult, CentralMessage};
-use std::sync::mpsc::{TryRecvError, Sender, Receiver, channel};
+use super::tracer::{TraceEvent, Endpoint, Payload};
+use super::checkpoint::{Checkpoint};
+use node::{Message, NodeResult, CentralMessage};
+use serde::{Serialize, Deserialize};
+use crate::log_event;
+use crate::logger::{Level};
+use std::sync::mpsc::{TryRecvError, Sender, Receiver};
 use std::collections::{HashSet};
-use std::sync::{Mutex, Arc};
+use std::time::{Duration, Instant};
 
 pub struct Coordinator {
     pub stage: Stage,    
     pub round: usize,
     pub nodes_sender: Vec<Sender<Message>>,
     pub result_list: Vec<NodeResult>,
+    pub decide_round: Vec<Option<usize>>,
+    pub history: Vec<RoundState>,
+    pub current_round: RoundState,
     pub num_node: usize,
     pub central_receiver: Receiver<CentralMessage>,
+    pub is_active: Vec<bool>,
+    pub curr_num_node: usize,
+    pub stage1_num_message: usize,
+    pub stage2_num_message: usize,
+    pub num_reconfig_message: usize,
+    pub num_reconfig_node: usize,
+    pub nodes_to_remove: HashSet<usize>,
+    pub num_bit: usize, //bits sent by the nodes, counted by the bit-level algorithm only
+    pub colors: Vec<Option<usize>>, //kept by the coloring nodes
+    pub partners: Vec<Option<usize>>, //matched by the matching nodes
+    pub snapshot_round: Option<usize>, //rounds after which the undecided nodes are recorded
+    pub is_stopping: bool, //the run ends at the snapshot, undecided nodes leave without result
+    pub residual: Option<Vec<usize>>, //undecided nodes at the snapshot
+    pub checkpoint: Option<Checkpoint>, //written every checkpoint_every rounds
+    pub checkpoint_path: String,
+    pub checkpoint_every: usize,
+    pub num_state: usize, //node states the checkpoint still waits for
+    pub num_checkpoint: usize,
+    pub num_failed_checkpoint: usize, //writes that failed, the run goes on without them
+    pub deadline: Option<Instant>, //the run stops there, even in the middle of a round
+    pub is_timed_out: bool,
+    pub is_disconnected: bool, //every sender is gone before the end, nothing can arrive anymore
+    pub tracer: Option<Sender<TraceEvent>>,
 }
 
+// what happened to the nodes during one round
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct RoundState {
+    pub round: usize,
+    pub marked: Vec<usize>,
+    pub joined: Vec<usize>, //joined the mis, kept its color or got matched
+    pub removed: Vec<usize>, //left the network next to a mis node
+}
+
+impl RoundState {
+    pub fn new(round: usize) -> RoundState {
+        RoundState {
+            round,
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum Stage {
     Round1,
     Round2,
@@ -29,117 +79,333 @@ impl Coordinator {
         Coordinator {
             stage: Stage::Start,
             round: 0,
-            nodes_sender: nodes_sender,
+            nodes_sender,
             result_list: Vec::new(),
-            num_node: num_node,
-            central_receiver: central_receiver
+            decide_round: vec![None; num_node],
+            history: Vec::new(),
+            current_round: RoundState::new(0),
+            num_node,
+            central_receiver,
+            is_active: vec![true; num_node],
+            curr_num_node: num_node,
+            stage1_num_message: 0,
+            stage2_num_message: 0,
+            num_reconfig_message: 0,
+            num_reconfig_node: 0,
+            nodes_to_remove: HashSet::new(),
+            num_bit: 0,
+            colors: vec![None; num_node],
+            partners: vec![None; num_node],
+            snapshot_round: None,
+            is_stopping: false,
+            residual: None,
+            checkpoint: None,
+            checkpoint_path: String::new(),
+            checkpoint_every: 0,
+            num_state: 0,
+            num_checkpoint: 0,
+            num_failed_checkpoint: 0,
+            deadline: None,
+            is_timed_out: false,
+            is_disconnected: false,
+            tracer: None,
         } 
     }
 
-    pub fn start_next_round(&mut self) {
-        for sender in  self.nodes_sender.iter_mut() {
-            sender.send(Message::Start(self.round)); 
+    // a node that has left the network has dropped its receiver, so a failed send is expected
+    fn send_active(&mut self, message: Message) {
+        for (node_id, sender) in self.nodes_sender.iter_mut().enumerate() {
+            if self.is_active[node_id] {
+                let _ = sender.send(message.clone());
+            }
         }
     }
 
+    pub fn start_next_round(&mut self) {
+        let round = self.round;
+        self.send_active(Message::Start(round));
+    }
+
     pub fn inform_nodes(&mut self) {
-        for sender in self.nodes_sender.iter_mut() {
-            sender.send(Message::StartRound2); 
-        }
+        self.send_active(Message::StartRound2);
     }
 
     pub fn start(&mut self) {
-        let mut curr_num_node = self.num_node;
-        let mut stage1_num_message = 0;
-        let mut stage2_num_message = 0;
-        let mut num_reconfig_message = 0;
-        let mut num_reconfig_node = 0;
-        let mut nodes_to_remove: HashSet<usize> = HashSet::new();
-        
         loop {
-            if self.result_list.len() == self.num_node {
+            if !self.is_finished() && self.deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                self.time_out();
+            }
+            if self.is_finished() {
                 //let mut re: HashSet<usize> = HashSet::new();
                 //for i in 0..self.num_node {
                 //    re.insert(i); 
                 //}
                 //self.remove_neighbors(&re);
+                self.finish();
                 break;            
             }
-
-            match self.stage {
-                Stage::Start => {
-                    //println!("Coordinator Start");
-                    self.stage = Stage::Round1;
-                    self.start_next_round();             
-                    
-                },
-                Stage::Round1 => {
-                    if stage1_num_message == curr_num_node {
-                        //println!("Coordinator Round 1 all collected, {} {} ", stage1_num_message, curr_num_node);
-                        self.stage = Stage::Round2;
-                        stage1_num_message = 0;
-                        self.inform_nodes();
-                    } 
-                },
-                Stage::Round2 => {
-                    if stage2_num_message == curr_num_node {
-                        //println!("Coordinator Round 2 all collected");
-                        self.stage = Stage::Reconfigure;
-                        stage2_num_message = 0;
-                        num_reconfig_node = self.remove_neighbors(&nodes_to_remove);
-                        //println!("num reconfig node {}", num_reconfig_node); 
-                    }
-                },
-                Stage::Reconfigure => {
-                    if num_reconfig_message == num_reconfig_node {
-                        curr_num_node =curr_num_node - nodes_to_remove.len();
-                        //println!("Coordinator Stage::Reconfigure  all collected. Curr num node {}", curr_num_node);
-                        self.stage = Stage::Start; 
-                        num_reconfig_message = 0;
-                        num_reconfig_node = 0;
-                        nodes_to_remove.clear();
-                        self.round += 1;
-                    }
-                }
+            if self.is_disconnected {
+                log_event!(Level::Warn, self.round, None, Some(self.stage), "Disconnected", "decided={}", self.result_list.len());
+                break;
             }
+
+            self.advance();
     
             // update
             match self.central_receiver.try_recv() {
-                Ok(central_message) => {
-                    match central_message {
-                        CentralMessage::Step(node_id)=> {
-                            stage2_num_message += 1;
-                        },
-                        CentralMessage::Finish(result) => {
-                            stage2_num_message += 1;
-                            nodes_to_remove.insert(result.id);
-                            for id in result.nodes_to_remove.iter() {
-                                nodes_to_remove.insert(*id);
-                            }
-                            self.result_list.push(result);  
-                        },
-                        CentralMessage::Round1Complete => {
-                            stage1_num_message += 1;         
-                        },
-                        CentralMessage::ReconfigComplete(result) => {
-                            num_reconfig_message += 1;
-                            nodes_to_remove.insert(result.id);
-                            let node_result = NodeResult {
-                                id: result.id,
-                                is_in_mis: true,
-                                nodes_to_remove: vec![],
-                            };
-                            self.result_list.push(node_result);
-                        }
-                    }
-                },
+                Ok(central_message) => self.handle_central_message(central_message),
                 Err(TryRecvError::Empty) =>(),
+                Err(TryRecvError::Disconnected) => self.is_disconnected = true,
+            }
+        } 
+        log_event!(Level::Info, self.round, None, Some(self.stage), "Finish", "mis_size={}", self.get_mis_result().len());
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.result_list.len() == self.num_node || (self.is_stopping && self.residual.is_some())
+    }
+
+    pub fn set_snapshot(&mut self, round: usize, is_stopping: bool) {
+        self.snapshot_round = Some(round);
+        self.is_stopping = is_stopping;
+        if round == 0 {
+            self.take_snapshot();
+        }
+    }
+
+    pub fn set_time_limit(&mut self, limit: Duration) {
+        self.deadline = Some(Instant::now() + limit);
+    }
+
+    // stops like at a snapshot, the undecided nodes and those decided in this round are
+    // told to leave at finish, whatever they are waiting for
+    fn time_out(&mut self) {
+        log_event!(Level::Warn, self.round, None, Some(self.stage), "TimeOut", "decided={} undecided={}", self.result_list.len(), self.num_node - self.result_list.len());
+        self.is_timed_out = true;
+        self.is_stopping = true;
+        self.take_snapshot();
+    }
+
+    // the checkpoint holds the run settings, the coordinator fills in the rest
+    pub fn set_checkpoint(&mut self, path: &str, every: usize, checkpoint: Checkpoint) {
+        self.checkpoint_path = path.to_string();
+        self.checkpoint_every = every.max(1);
+        self.checkpoint = Some(checkpoint);
+    }
+
+    // continues before the round of the checkpoint, whose undecided nodes were rebuilt
+    // by the caller, the other nodes have left
+    pub fn resume(&mut self, checkpoint: &Checkpoint) {
+        self.round = checkpoint.round;
+        self.result_list = checkpoint.results.clone();
+        self.decide_round = checkpoint.decide_round.clone();
+        self.history = checkpoint.history.clone();
+        self.current_round = RoundState::new(checkpoint.round);
+        self.num_bit = checkpoint.num_bit;
+        self.is_active = vec![false; self.num_node];
+        for state in checkpoint.nodes.iter() {
+            self.is_active[state.id] = true;
+        }
+        self.curr_num_node = checkpoint.nodes.len();
+    }
+
+    // between two rounds every undecided node waits for Start, so their states and the
+    // results form a consistent cut
+    fn request_states(&mut self) {
+        if let Some(checkpoint) = self.checkpoint.as_mut() {
+            checkpoint.nodes.clear();
+            self.num_state = self.curr_num_node;
+            self.send_active(Message::Checkpoint);
+        }
+    }
+
+    fn store_checkpoint(&mut self) {
+        if let Some(checkpoint) = self.checkpoint.as_mut() {
+            checkpoint.round = self.round;
+            checkpoint.results = self.result_list.clone();
+            checkpoint.decide_round = self.decide_round.clone();
+            checkpoint.history = self.history.clone();
+            checkpoint.num_bit = self.num_bit;
+            checkpoint.nodes.sort_by_key(|state| state.id);
+            // a failed write leaves the previous checkpoint, losing the run would be worse
+            match checkpoint.store(&self.checkpoint_path) {
+                Ok(()) => {
+                    self.num_checkpoint += 1;
+                    log_event!(Level::Info, self.round, None, Some(self.stage), "Checkpoint", "path={} undecided={}", self.checkpoint_path, checkpoint.nodes.len());
+                },
                 Err(e) => {
-                    //println!("result receiver try receive error {:?}", e); 
+                    self.num_failed_checkpoint += 1;
+                    log_event!(Level::Warn, self.round, None, Some(self.stage), "Checkpoint", "path={} error={}", self.checkpoint_path, e);
                 },
             }
-        } 
-        //println!("simulation finishes");
+        }
+    }
+
+    // when stopping, the undecided nodes are told to leave at finish, nodes decided in
+    // the current round are still active until it ends
+    fn take_snapshot(&mut self) {
+        let residual: Vec<usize> = (0..self.num_node).filter(|id| self.is_active[*id] && self.decide_round[*id].is_none()).collect();
+        log_event!(Level::Info, self.round, None, Some(self.stage), "Snapshot", "undecided={}", residual.len());
+        if self.is_stopping {
+            self.nodes_to_remove.extend(residual.iter().cloned());
+        }
+        self.residual = Some(residual);
+    }
+
+    // let the nodes decided in the last round exit as well, the trace ends before
+    // they receive it
+    pub fn finish(&mut self) {
+        self.close_round();
+        self.record(TraceEvent::End(self.get_sorted_mis()));
+        let nodes_to_remove = self.nodes_to_remove.clone();
+        self.remove_neighbors(&nodes_to_remove);
+    }
+
+    // move to the next stage once every expected message is collected, returns true on a transition
+    pub fn advance(&mut self) -> bool {
+        match self.stage {
+            Stage::Start => {
+                if self.num_state > 0 {
+                    return false;
+                }
+                self.set_stage(Stage::Round1);
+                self.start_next_round();             
+            },
+            Stage::Round1 => {
+                if self.stage1_num_message != self.curr_num_node {
+                    return false;
+                }
+                self.set_stage(Stage::Round2);
+                self.stage1_num_message = 0;
+                self.inform_nodes();
+            },
+            Stage::Round2 => {
+                if self.stage2_num_message != self.curr_num_node {
+                    return false;
+                }
+                self.set_stage(Stage::Reconfigure);
+                self.stage2_num_message = 0;
+                let nodes_to_remove = self.nodes_to_remove.clone();
+                self.num_reconfig_node = self.remove_neighbors(&nodes_to_remove);
+                log_event!(Level::Debug, self.round, None, Some(self.stage), "RemoveNeighbors",
+                    "removed={} notified={}", nodes_to_remove.len(), self.num_reconfig_node);
+            },
+            Stage::Reconfigure => {
+                if self.num_reconfig_message != self.num_reconfig_node {
+                    return false;
+                }
+                self.curr_num_node -= self.nodes_to_remove.len();
+                for node_id in self.nodes_to_remove.iter() {
+                    self.is_active[*node_id] = false;
+                }
+                self.close_round();
+                self.round += 1;
+                self.set_stage(Stage::Start); 
+                self.num_reconfig_message = 0;
+                self.num_reconfig_node = 0;
+                self.nodes_to_remove.clear();
+                if self.snapshot_round == Some(self.round) {
+                    self.take_snapshot();
+                }
+                if self.checkpoint_every > 0 && self.round.is_multiple_of(self.checkpoint_every) && !self.is_finished() {
+                    self.request_states();
+                }
+            }
+        }
+        true
+    }
+
+    pub fn handle_central_message(&mut self, central_message: CentralMessage) {
+        if let Some(tracer) = self.tracer.as_ref() {
+            let _ = tracer.send(TraceEvent::Delivery {
+                round: self.round,
+                from: Endpoint::Node(central_message.sender_id()),
+                to: Endpoint::Coordinator,
+                payload: Payload::Central(central_message.clone()),
+            });
+        }
+        log_event!(Level::Trace, self.round, Some(central_message.sender_id()), Some(self.stage), central_message.kind(), "to=coordinator message={:?}", central_message);
+        match central_message {
+            CentralMessage::Step(_node_id)=> {
+                self.stage2_num_message += 1;
+            },
+            CentralMessage::Finish(result) => {
+                self.stage2_num_message += 1;
+                self.nodes_to_remove.insert(result.id);
+                for id in result.nodes_to_remove.iter() {
+                    self.nodes_to_remove.insert(*id);
+                }
+                self.decide_round[result.id] = Some(self.round);
+                self.colors[result.id] = result.color;
+                self.partners[result.id] = result.partner;
+                if result.is_in_mis || result.color.is_some() || result.partner.is_some() {
+                    self.current_round.joined.push(result.id);
+                } else {
+                    self.current_round.removed.push(result.id);
+                }
+                self.result_list.push(result);  
+            },
+            CentralMessage::Round1Complete((node_id, is_marked, num_bit)) => {
+                self.stage1_num_message += 1;         
+                self.num_bit += num_bit;
+                if is_marked {
+                    self.current_round.marked.push(node_id);
+                }
+            },
+            CentralMessage::ReconfigComplete(result) => {
+                self.num_reconfig_message += 1;
+                // only an isolated node leaves during reconfiguration, and it joins the MIS
+                if result.is_leaving {
+                    self.nodes_to_remove.insert(result.id);
+                    let node_result = NodeResult {
+                        id: result.id,
+                        is_in_mis: true,
+                        nodes_to_remove: vec![],
+                        color: None,
+                        partner: None,
+                    };
+                    self.decide_round[result.id] = Some(self.round);
+                    self.current_round.joined.push(result.id);
+                    self.result_list.push(node_result);
+                }
+            },
+            CentralMessage::State(state) => {
+                if let Some(checkpoint) = self.checkpoint.as_mut() {
+                    checkpoint.nodes.push(state);
+                }
+                self.num_state -= 1;
+                if self.num_state == 0 {
+                    self.store_checkpoint();
+                }
+            },
+        }
+    }
+
+    fn close_round(&mut self) {
+        let state = std::mem::replace(&mut self.current_round, RoundState::new(self.round + 1));
+        self.history.push(state);
+    }
+
+    fn set_stage(&mut self, stage: Stage) {
+        log_event!(Level::Info, self.round, None, Some(stage), "Stage", "active_nodes={} decided={}", self.curr_num_node, self.result_list.len());
+        self.stage = stage;
+        self.record(TraceEvent::Stage { round: self.round, stage });
+    }
+
+    fn record(&self, event: TraceEvent) {
+        if let Some(tracer) = self.tracer.as_ref() {
+            let _ = tracer.send(event);
+        }
+    }
+
+    pub fn set_tracer(&mut self, tracer: Sender<TraceEvent>) {
+        self.tracer = Some(tracer);
+    }
+
+    pub fn get_sorted_mis(&self) -> Vec<usize> {
+        let mut mis: Vec<usize> = self.get_mis_result().into_iter().collect();
+        mis.sort();
+        mis
     }
 
     pub fn get_mis_result(&self) -> HashSet<usize> {
@@ -155,15 +421,21 @@ impl Coordinator {
 
 
     // channel is FIFO
+    // removed nodes are notified too so that their threads exit, but only the
+    // remaining ones reply with ReconfigComplete
     pub fn remove_neighbors(&mut self, nodes_to_remove: &HashSet<usize>) -> usize {
         let mut num_node_notified = 0;
+        if nodes_to_remove.is_empty() {
+            return 0;
+        }
         for node_id in 0..self.nodes_sender.len() {
+            if !self.is_active[node_id] {
+                continue;
+            }
             let sender = &mut self.nodes_sender[node_id];
-            if nodes_to_remove.len() > 0 {
-                if ! nodes_to_remove.contains(&node_id) {
-                    sender.send(Message::RemoveNeighbors(nodes_to_remove.clone())); 
-                    num_node_notified += 1;
-                }
+            let _ = sender.send(Message::RemoveNeighbors(nodes_to_remove.clone())); 
+            if ! nodes_to_remove.contains(&node_id) {
+                num_node_notified += 1;
             }
         }
         num_node_notified
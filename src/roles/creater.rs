@@ -1,23 +1,39 @@
 extern crate rand;
-use rand::{Rng, thread_rng};
+use rand::{Rng};
+use super::dynamic::{Update};
+use super::stream::{UpdateStream, TimedOperation, Operation};
+use super::graph::{undirected_edges};
+use std::collections::{HashSet};
 use std::fs::File;
-use std::io::{BufWriter, Write, Result};
-use bincode::serialize;
+use std::io::{BufRead, BufReader, BufWriter, Write, Result, Error, ErrorKind};
 
 pub struct Creater {
     pub graph: Vec<Vec<usize>>,
+    pub weights: Vec<f32>, //node weights, 1 unless given
+}
+
+impl Default for Creater {
+    fn default() -> Creater {
+        Creater::new()
+    }
 }
 
 impl Creater {
     pub fn new() -> Creater {
         Creater {
             graph: Vec::new(), 
+            weights: Vec::new(),
         } 
     }
 
     pub fn generate(&mut self, num_node: usize, num_degree: usize) -> Vec<Vec<usize>> {
+        self.generate_with(num_node, num_degree, &mut rand::thread_rng())
+    }
+
+    // same graphs as generate, drawn from the given rng so that a seed reproduces them
+    pub fn generate_with<R: Rng>(&mut self, num_node: usize, num_degree: usize, rng: &mut R) -> Vec<Vec<usize>> {
         self.graph.clear();
-        let mut rng = rand::thread_rng();
+        self.weights = vec![1.0; num_node];
         for _ in 0..num_node {
             self.graph.push(Vec::new())
         }
@@ -38,25 +54,122 @@ impl Creater {
         self.graph.clone()
     }
 
-    pub fn get_graph(&self) -> Vec<Vec<usize>>  {
-        self.graph.clone() 
+    // integer weights drawn uniformly from 1..=max_weight
+    pub fn random_weights(&mut self, max_weight: usize) -> Vec<f32> {
+        self.random_weights_with(max_weight, &mut rand::thread_rng())
+    }
+
+    pub fn random_weights_with<R: Rng>(&mut self, max_weight: usize, rng: &mut R) -> Vec<f32> {
+        self.weights = (0..self.graph.len()).map(|_| rng.gen_range(1, max_weight + 1) as f32).collect();
+        self.weights.clone()
     }
 
+    // weights are only written when some node is not of weight 1
     pub fn store_graph(&self, filename: String) -> Result<()> {
         let f = File::create(filename).expect("Unable to open file");
         let mut f = BufWriter::new(f);
+        let is_weighted = self.weights.iter().any(|w| *w != 1.0);
         for i in 0..self.graph.len() {
-            write!(f, "{} ", i);
+            if is_weighted {
+                write!(f, "{}:{} ", i, self.weights[i])?;
+            } else {
+                write!(f, "{} ", i)?;
+            }
             for j in self.graph[i].iter() {
-                let ser_j = serialize(j).unwrap();
-                write!(f, "{}", j);
-                write!(f, " ");
+                write!(f, "{}", j)?;
+                write!(f, " ")?;
             } 
-            write!(f, "\n");
+            writeln!(f)?;
         }
         Ok(())
     }
 
+    // reads an adjacency list as written by store_graph, one line per node starting at 0,
+    // the first column being the src node id, optionally followed by :weight
+    pub fn load_graph(&mut self, filename: &str) -> Result<Vec<Vec<usize>>> {
+        self.graph.clear();
+        self.weights.clear();
+        let f = BufReader::new(File::open(filename)?);
+        for line in f.lines() {
+            let line = line?;
+            let weight = match line.split_whitespace().next().and_then(|src| src.split(':').nth(1)) {
+                Some(weight) => weight.parse::<f32>().map_err(|_| invalid_line(self.graph.len(), "invalid node weight", weight))?,
+                None => 1.0,
+            };
+            self.weights.push(weight);
+            let mut dsts: Vec<usize> = vec![];
+            for token in line.split_whitespace().skip(1) {
+                let n_id = token.parse::<usize>().map_err(|_| invalid_line(self.graph.len(), "invalid node id", token))?;
+                dsts.push(n_id);
+            }
+            self.graph.push(dsts);
+        }
+        if let Some((id, n_id)) = self.graph.iter().enumerate().find_map(|(id, dsts)| dsts.iter().find(|n_id| **n_id >= self.graph.len()).map(|n_id| (id, *n_id))) {
+            return Err(invalid_line(id, "node id out of range", &n_id.to_string()));
+        }
+        Ok(self.graph.clone())
+    }
+
+    // updates that stay valid on the graph they change, edges are added and removed
+    // more often than nodes, and a new node gets up to num_degree random neighbors
+    pub fn random_updates(&self, num_update: usize, num_degree: usize) -> Vec<Update> {
+        let mut rng = rand::thread_rng();
+        let mut edges = undirected_edges(&self.graph);
+        let mut edge_set: HashSet<(usize, usize)> = edges.iter().cloned().collect();
+        let mut nodes: Vec<usize> = (0..self.graph.len()).collect();
+        let mut next_id = self.graph.len();
+        let mut updates: Vec<Update> = vec![];
+        while updates.len() < num_update {
+            let kind = rng.gen_range(0, 20);
+            if kind < 7 && nodes.len() >= 2 {
+                let u = nodes[rng.gen_range(0, nodes.len())];
+                let v = nodes[rng.gen_range(0, nodes.len())];
+                if u != v && edge_set.insert((u.min(v), u.max(v))) {
+                    edges.push((u.min(v), u.max(v)));
+                    updates.push(Update::AddEdge(u, v));
+                }
+            } else if kind < 14 && !edges.is_empty() {
+                let (u, v) = edges.swap_remove(rng.gen_range(0, edges.len()));
+                edge_set.remove(&(u, v));
+                updates.push(Update::RemoveEdge(u, v));
+            } else if kind < 17 {
+                let mut dsts: Vec<usize> = vec![];
+                for _ in 0..rng.gen_range(0, num_degree.max(1) + 1).min(nodes.len()) {
+                    let n_id = nodes[rng.gen_range(0, nodes.len())];
+                    if !dsts.contains(&n_id) {
+                        dsts.push(n_id);
+                    }
+                }
+                for n_id in dsts.iter() {
+                    edge_set.insert((*n_id, next_id));
+                    edges.push((*n_id, next_id));
+                }
+                nodes.push(next_id);
+                updates.push(Update::AddNode(next_id, dsts));
+                next_id += 1;
+            } else if !nodes.is_empty() {
+                let id = nodes.swap_remove(rng.gen_range(0, nodes.len()));
+                edges.retain(|(u, v)| *u != id && *v != id);
+                edge_set.retain(|(u, v)| *u != id && *v != id);
+                updates.push(Update::RemoveNode(id));
+            }
+        }
+        updates
+    }
+
+    // batch i holds batch_size random updates at time i and ends with a query
+    pub fn random_stream(&self, num_batch: usize, batch_size: usize, num_degree: usize) -> UpdateStream {
+        let updates = self.random_updates(num_batch * batch_size, num_degree);
+        let mut operations: Vec<TimedOperation> = vec![];
+        for (time, batch) in updates.chunks(batch_size.max(1)).enumerate() {
+            for update in batch.iter() {
+                operations.push(TimedOperation { time: time as u64, operation: Operation::Update(update.clone()) });
+            }
+            operations.push(TimedOperation { time: time as u64, operation: Operation::Query });
+        }
+        UpdateStream { operations }
+    }
+
     pub fn get_max_degree(&self) -> usize {
         let mut max_degree = 0;
         for i in 0..self.graph.len() {
@@ -65,6 +178,11 @@ impl Creater {
                 max_degree = neighbor_len;
             }
         }
-        return max_degree;
+        max_degree
     }
 }
+
+// index is the 0 based line, the node id it describes
+fn invalid_line(index: usize, reason: &str, token: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, format!("line {}: {} {}", index + 1, reason, token))
+}
@@ -0,0 +1,115 @@
+use serde::{Serialize, Deserialize};
+
+use std::fs;
+use std::io::{Result, Error, ErrorKind};
+
+// how the desire level of a luby or weighted node moves between rounds: divided by down
+// when the desire levels of its neighbors sum to threshold or more, multiplied by up
+// otherwise, never above cap nor below floor
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DesireConfig {
+    pub initial: f32,
+    pub up: f32,
+    pub down: f32,
+    pub cap: f32,
+    pub threshold: f32, //effective degree
+    pub floor: Option<f32>,
+}
+
+impl Default for DesireConfig {
+    // halving and doubling up to 1/2 around an effective degree of 2
+    fn default() -> DesireConfig {
+        DesireConfig {
+            initial: 0.5,
+            up: 2.0,
+            down: 2.0,
+            cap: 0.5,
+            threshold: 2.0,
+            floor: None,
+        }
+    }
+}
+
+impl DesireConfig {
+    pub fn update(&self, desire_level: f32, effective_degree: f32) -> f32 {
+        if effective_degree >= self.threshold {
+            let lowered = desire_level / self.down;
+            match self.floor {
+                Some(floor) => lowered.max(floor),
+                None => lowered,
+            }
+        } else {
+            (self.up * desire_level).min(self.cap)
+        }
+    }
+
+    // one key=value setting, floor=none removes the floor
+    pub fn set(&mut self, key: &str, value: &str) -> std::result::Result<(), String> {
+        let value = value.trim();
+        if key.trim() == "floor" && value == "none" {
+            self.floor = None;
+            return Ok(());
+        }
+        let number = value.parse::<f32>().map_err(|_| format!("invalid desire {} value {}", key.trim(), value))?;
+        match key.trim() {
+            "initial" => self.initial = number,
+            "up" => self.up = number,
+            "down" => self.down = number,
+            "cap" => self.cap = number,
+            "threshold" => self.threshold = number,
+            "floor" => self.floor = Some(number),
+            key => return Err(format!("unknown desire parameter {}, expected initial, up, down, cap, threshold or floor", key)),
+        }
+        Ok(())
+    }
+
+    // key=value settings separated by commas or lines, # starts a comment, the others
+    // keep their value
+    pub fn apply(&mut self, settings: &str) -> std::result::Result<(), String> {
+        for line in settings.lines() {
+            let line = line.split('#').next().unwrap_or("");
+            for setting in line.split(',').filter(|s| !s.trim().is_empty()) {
+                match setting.split_once('=') {
+                    Some((key, value)) => self.set(key, value)?,
+                    None => return Err(format!("desire setting {} is not key=value", setting.trim())),
+                }
+            }
+        }
+        self.check()
+    }
+
+    // a file of key = value lines over the defaults
+    pub fn load(filename: &str) -> Result<DesireConfig> {
+        let mut config = DesireConfig::default();
+        config.apply(&fs::read_to_string(filename)?).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+        Ok(config)
+    }
+
+    // a desire level stays a probability, and a node always lowers it when crowded, nan
+    // fails every comparison
+    pub fn check(&self) -> std::result::Result<(), String> {
+        let is_above = |value: f32, bound: f32| value > bound;
+        let is_probability = |value: f32| is_above(value, 0.0) && value <= 1.0;
+        if !is_probability(self.initial) || !is_probability(self.cap) {
+            return Err(format!("desire initial {} and cap {} must be in (0, 1]", self.initial, self.cap));
+        }
+        if !(1.0..).contains(&self.up) || !is_above(self.down, 1.0) {
+            return Err(format!("desire up {} must be at least 1 and down {} above 1", self.up, self.down));
+        }
+        if !is_above(self.threshold, 0.0) {
+            return Err(format!("desire threshold {} must be positive", self.threshold));
+        }
+        if let Some(floor) = self.floor.filter(|floor| !is_probability(*floor) || *floor > self.cap) {
+            return Err(format!("desire floor {} must be in (0, cap]", floor));
+        }
+        Ok(())
+    }
+
+    pub fn describe(&self) -> String {
+        let floor = match self.floor {
+            Some(floor) => floor.to_string(),
+            None => "none".to_string(),
+        };
+        format!("initial={},up={},down={},cap={},threshold={},floor={}", self.initial, self.up, self.down, self.cap, self.threshold, floor)
+    }
+}
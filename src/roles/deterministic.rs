@@ -0,0 +1,126 @@
+use super::coordinator::{RoundState};
+use super::graph::{undirected_neighbors};
+use std::collections::{HashSet};
+
+// randomness free mis in synchronous rounds, every round each active node sends its
+// color or decision to its neighbors:
+// 1. starting from the node ids, colors are reduced to max degree + 1 by splitting the
+//    palette into groups of 2(max degree + 1) colors and recoloring, in parallel inside
+//    every group, one excess color per round with the smallest free color of the group
+//    (Kuhn-Wattenhofer), which halves the number of colors every max degree + 1 rounds
+// 2. color classes join the mis one after the other, a node joins when no neighbor did
+pub struct DeterministicMis {
+    pub neighbors: Vec<Vec<usize>>,
+    pub palette: usize, //max degree + 1
+    pub colors: Vec<usize>,
+    pub num_color: usize,
+    pub color_round: usize,
+    pub mis_round: usize,
+    pub num_message: usize,
+    pub is_in_mis: Vec<bool>,
+    pub decide_round: Vec<Option<usize>>,
+    pub history: Vec<RoundState>,
+}
+
+impl DeterministicMis {
+    pub fn new(graph: &[Vec<usize>]) -> DeterministicMis {
+        let neighbors = undirected_neighbors(graph);
+        let max_degree = neighbors.iter().map(|n| n.len()).max().unwrap_or(0);
+        DeterministicMis {
+            neighbors,
+            palette: max_degree + 1,
+            colors: (0..graph.len()).collect(),
+            num_color: graph.len(),
+            color_round: 0,
+            mis_round: 0,
+            num_message: 0,
+            is_in_mis: vec![false; graph.len()],
+            decide_round: vec![None; graph.len()],
+            history: vec![],
+        }
+    }
+
+    fn round(&self) -> usize {
+        self.color_round + self.mis_round
+    }
+
+    // every node tells its neighbors its color or decision
+    fn exchange(&mut self, is_active: &[bool]) {
+        for (id, neighbors) in self.neighbors.iter().enumerate() {
+            if is_active[id] {
+                self.num_message += neighbors.len();
+            }
+        }
+    }
+
+    pub fn reduce_colors(&mut self) {
+        let group_size = 2 * self.palette;
+        let is_active = vec![true; self.neighbors.len()];
+        while self.num_color > self.palette {
+            for excess in self.palette..group_size.min(self.num_color) {
+                self.exchange(&is_active);
+                let colors = self.colors.clone();
+                for id in 0..self.neighbors.len() {
+                    if colors[id] % group_size != excess {
+                        continue;
+                    }
+                    let group = colors[id] / group_size;
+                    let used: HashSet<usize> = self.neighbors[id].iter()
+                        .filter(|n_id| colors[**n_id] / group_size == group)
+                        .map(|n_id| colors[*n_id] % group_size)
+                        .collect();
+                    let free = (0..self.palette).find(|c| !used.contains(c)).unwrap();
+                    self.colors[id] = group * group_size + free;
+                }
+                self.history.push(RoundState::new(self.round()));
+                self.color_round += 1;
+            }
+            // every group now uses its first palette colors only
+            for color in self.colors.iter_mut() {
+                *color = *color / group_size * self.palette + *color % group_size;
+            }
+            self.num_color = self.num_color.div_ceil(group_size) * self.palette;
+        }
+    }
+
+    pub fn select(&mut self) {
+        let num_node = self.neighbors.len();
+        let mut is_active = vec![true; num_node];
+        for color in 0..self.palette {
+            if !is_active.iter().any(|a| *a) {
+                break;
+            }
+            let round = self.round();
+            let mut state = RoundState::new(round);
+            self.exchange(&is_active);
+            for (id, is_node_active) in is_active.iter().enumerate() {
+                if *is_node_active && self.colors[id] == color {
+                    self.is_in_mis[id] = true;
+                    state.joined.push(id);
+                }
+            }
+            for id in state.joined.iter() {
+                is_active[*id] = false;
+                self.decide_round[*id] = Some(round);
+                for n_id in self.neighbors[*id].iter() {
+                    if is_active[*n_id] {
+                        is_active[*n_id] = false;
+                        self.decide_round[*n_id] = Some(round);
+                        state.removed.push(*n_id);
+                    }
+                }
+            }
+            self.history.push(state);
+            self.mis_round += 1;
+        }
+    }
+
+    pub fn run(&mut self) {
+        self.reduce_colors();
+        self.select();
+    }
+
+    pub fn get_mis_result(&self) -> HashSet<usize> {
+        (0..self.neighbors.len()).filter(|id| self.is_in_mis[*id]).collect()
+    }
+}
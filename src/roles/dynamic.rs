@@ -0,0 +1,220 @@
+use super::node::{Algorithm};
+use super::runner::{RunConfig, run_threaded};
+use super::verifier::{Verifier};
+use super::graph::{undirected_neighbors};
+use super::desire::{DesireConfig};
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Update {
+    AddEdge(usize, usize),
+    RemoveEdge(usize, usize),
+    AddNode(usize, Vec<usize>), //the new node takes the next free id, with its neighbors
+    RemoveNode(usize),
+}
+
+// the syntax of the update stream files
+impl fmt::Display for Update {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Update::AddEdge(u, v) => write!(f, "add-edge {} {}", u, v),
+            Update::RemoveEdge(u, v) => write!(f, "remove-edge {} {}", u, v),
+            Update::AddNode(id, dsts) => {
+                write!(f, "add-node {}", id)?;
+                for n_id in dsts.iter() {
+                    write!(f, " {}", n_id)?;
+                }
+                Ok(())
+            },
+            Update::RemoveNode(id) => write!(f, "remove-node {}", id),
+        }
+    }
+}
+
+// what one update cost to repair
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Adjustment {
+    pub affected: usize, //undecided nodes the algorithm re-ran on
+    pub changed: usize, //nodes that joined or left the mis
+    pub round: usize, //rounds of the distributed repair
+}
+
+// keeps an mis valid while the graph changes: an update only undecides the nodes it
+// may have broken, which have no mis neighbor left, and the distributed algorithm runs
+// on the graph they induce
+pub struct DynamicMis {
+    pub neighbors: Vec<HashSet<usize>>,
+    pub is_present: Vec<bool>,
+    pub mis: HashSet<usize>,
+    pub weights: Vec<f32>, //new nodes weigh 1
+    pub algorithm: Algorithm,
+    pub desire: DesireConfig,
+    pub seed: u64,
+    pub num_update: usize,
+}
+
+impl DynamicMis {
+    pub fn new(graph: &[Vec<usize>], weights: &[f32], algorithm: Algorithm, seed: u64) -> Result<DynamicMis, String> {
+        if matches!(algorithm, Algorithm::Coloring | Algorithm::Matching) {
+            return Err(format!("the dynamic mode repairs an mis, not a {}", algorithm.name()));
+        }
+        let neighbors: Vec<HashSet<usize>> = undirected_neighbors(graph).into_iter().map(|n| n.into_iter().collect()).collect();
+        Ok(DynamicMis {
+            neighbors,
+            is_present: vec![true; graph.len()],
+            mis: HashSet::new(),
+            weights: weights.to_vec(),
+            algorithm,
+            desire: DesireConfig::default(),
+            seed,
+            num_update: 0,
+        })
+    }
+
+    pub fn set_desire(&mut self, desire: DesireConfig) {
+        self.desire = desire;
+    }
+
+    // solves the whole graph, returns the rounds
+    pub fn initialize(&mut self) -> usize {
+        let nodes: Vec<usize> = (0..self.neighbors.len()).filter(|id| self.is_present[*id]).collect();
+        self.mis.clear();
+        self.repair(&nodes)
+    }
+
+    pub fn num_node(&self) -> usize {
+        self.is_present.iter().filter(|p| **p).count()
+    }
+
+    pub fn contains(&self, id: usize) -> bool {
+        id < self.is_present.len() && self.is_present[id]
+    }
+
+    fn is_covered(&self, id: usize) -> bool {
+        self.mis.contains(&id) || self.neighbors[id].iter().any(|n_id| self.mis.contains(n_id))
+    }
+
+    fn uncovered(&self, candidates: &[usize]) -> Vec<usize> {
+        let mut uncovered: Vec<usize> = candidates.iter().cloned()
+            .filter(|id| self.contains(*id) && !self.is_covered(*id))
+            .collect();
+        uncovered.sort();
+        uncovered.dedup();
+        uncovered
+    }
+
+    pub fn apply(&mut self, update: &Update) -> Result<Adjustment, String> {
+        if let Update::AddNode(id, _) = update {
+            if *id != self.neighbors.len() {
+                return Err(format!("new node {} is not the next free id {}", id, self.neighbors.len()));
+            }
+        }
+        let before = self.mis.clone();
+        let undecided: Vec<usize> = match update {
+            Update::AddEdge(u, v) => {
+                if *u == *v || !self.contains(*u) || !self.contains(*v) {
+                    vec![]
+                } else {
+                    self.neighbors[*u].insert(*v);
+                    self.neighbors[*v].insert(*u);
+                    // the larger id leaves, its neighbors may have relied on it
+                    if self.mis.contains(u) && self.mis.contains(v) {
+                        let evicted = *u.max(v);
+                        self.mis.remove(&evicted);
+                        let mut candidates: Vec<usize> = self.neighbors[evicted].iter().cloned().collect();
+                        candidates.push(evicted);
+                        self.uncovered(&candidates)
+                    } else {
+                        vec![]
+                    }
+                }
+            },
+            Update::RemoveEdge(u, v) => {
+                if self.contains(*u) && self.neighbors[*u].remove(v) {
+                    self.neighbors[*v].remove(u);
+                }
+                self.uncovered(&[*u, *v])
+            },
+            Update::AddNode(id, dsts) => {
+                self.neighbors.push(HashSet::new());
+                self.is_present.push(true);
+                self.weights.push(1.0);
+                for n_id in dsts.iter() {
+                    if *n_id != *id && self.contains(*n_id) {
+                        self.neighbors[*id].insert(*n_id);
+                        self.neighbors[*n_id].insert(*id);
+                    }
+                }
+                self.uncovered(&[*id])
+            },
+            Update::RemoveNode(id) => {
+                if !self.contains(*id) {
+                    vec![]
+                } else {
+                    self.is_present[*id] = false;
+                    self.mis.remove(id);
+                    let candidates: Vec<usize> = self.neighbors[*id].iter().cloned().collect();
+                    for n_id in candidates.iter() {
+                        self.neighbors[*n_id].remove(id);
+                    }
+                    self.neighbors[*id].clear();
+                    self.uncovered(&candidates)
+                }
+            },
+        };
+        let round = self.repair(&undecided);
+        self.num_update += 1;
+        Ok(Adjustment {
+            affected: undecided.len(),
+            changed: self.mis.symmetric_difference(&before).count(),
+            round,
+        })
+    }
+
+    // runs the distributed algorithm on the graph induced by the undecided nodes, which
+    // have no mis neighbor, a node without undecided neighbor joins on its own like an
+    // isolated node does during reconfiguration, returns the rounds
+    fn repair(&mut self, undecided: &[usize]) -> usize {
+        let is_undecided: HashSet<usize> = undecided.iter().cloned().collect();
+        let mut region: Vec<usize> = vec![];
+        for id in undecided.iter() {
+            if self.neighbors[*id].iter().any(|n_id| is_undecided.contains(n_id)) {
+                region.push(*id);
+            } else {
+                self.mis.insert(*id);
+            }
+        }
+        if region.is_empty() {
+            return 0;
+        }
+        let index: BTreeMap<usize, usize> = region.iter().enumerate().map(|(i, id)| (*id, i)).collect();
+        let graph: Vec<Vec<usize>> = region.iter().map(|id| {
+            let mut dsts: Vec<usize> = self.neighbors[*id].iter().filter_map(|n_id| index.get(n_id).cloned()).collect();
+            dsts.sort();
+            dsts
+        }).collect();
+        let seed = self.seed ^ (self.num_update as u64).wrapping_mul(0xD1B5_4A32_D192_ED03);
+        let weights: Vec<f32> = region.iter().map(|id| self.weights[*id]).collect();
+        let mut config = RunConfig::new(self.algorithm, seed);
+        config.desire = self.desire;
+        let outcome = run_threaded(&graph, &weights, &config);
+        for i in outcome.get_mis_result() {
+            self.mis.insert(region[i]);
+        }
+        outcome.round() + 1
+    }
+
+    // present nodes only, renumbered in increasing id order
+    pub fn verifier(&self) -> Verifier {
+        let nodes: Vec<usize> = (0..self.neighbors.len()).filter(|id| self.is_present[*id]).collect();
+        let index: BTreeMap<usize, usize> = nodes.iter().enumerate().map(|(i, id)| (*id, i)).collect();
+        let graph: Vec<Vec<usize>> = nodes.iter().map(|id| {
+            let mut dsts: Vec<usize> = self.neighbors[*id].iter().map(|n_id| index[n_id]).collect();
+            dsts.sort();
+            dsts
+        }).collect();
+        let mis: HashSet<usize> = self.mis.iter().map(|id| index[id]).collect();
+        Verifier::new(&mis, &graph)
+    }
+}
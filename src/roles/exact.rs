@@ -0,0 +1,228 @@
+use super::graph::{undirected_edges};
+use std::collections::{HashSet};
+
+// node sets of the remaining graph are bitsets, one bit per node
+type Bits = Vec<u64>;
+
+fn contains(bits: &[u64], id: usize) -> bool {
+    bits[id / 64] & (1 << (id % 64)) != 0
+}
+
+fn remove(bits: &mut [u64], id: usize) {
+    bits[id / 64] &= !(1 << (id % 64));
+}
+
+fn ones(bits: &[u64]) -> Vec<usize> {
+    let mut ids = vec![];
+    for (word_index, word) in bits.iter().enumerate() {
+        let mut word = *word;
+        while word != 0 {
+            ids.push(word_index * 64 + word.trailing_zeros() as usize);
+            word &= word - 1;
+        }
+    }
+    ids
+}
+
+fn count_common(a: &[u64], b: &[u64]) -> usize {
+    a.iter().zip(b.iter()).map(|(x, y)| (x & y).count_ones() as usize).sum()
+}
+
+// maximum independent set by branch and bound, meant for graphs up to a few hundred nodes:
+// isolated nodes are taken and nodes dominating a neighbor (N[u] within N[v]) are dropped,
+// disconnected parts are solved apart, the remaining graph is bounded by a greedy clique
+// cover (a coloring of the complement, each clique holds at most one node of the set) and
+// by the edge count, and the search branches on a node of maximum degree. with a branch
+// budget the search stops once it is spent and the best set found is only a lower bound
+pub struct ExactSolver {
+    pub adjacency: Vec<Bits>,
+    pub num_branch: usize,
+    pub max_branch: Option<usize>,
+    pub is_exhausted: bool, //the budget ran out before the search was complete
+}
+
+impl ExactSolver {
+    pub fn new(graph: &[Vec<usize>]) -> ExactSolver {
+        let num_node = graph.len();
+        let num_word = num_node.div_ceil(64);
+        let mut adjacency: Vec<Bits> = vec![vec![0; num_word]; num_node];
+        for (i, j) in undirected_edges(graph) {
+            adjacency[i][j / 64] |= 1 << (j % 64);
+            adjacency[j][i / 64] |= 1 << (i % 64);
+        }
+        ExactSolver {
+            adjacency,
+            num_branch: 0,
+            max_branch: None,
+            is_exhausted: false,
+        }
+    }
+
+    pub fn set_max_branch(&mut self, max_branch: usize) {
+        self.max_branch = Some(max_branch);
+    }
+
+    // returns a maximum independent set of the whole graph, or the largest one found when
+    // the budget runs out
+    pub fn solve(&mut self) -> HashSet<usize> {
+        let num_node = self.adjacency.len();
+        let mut remaining: Bits = vec![0; num_node.div_ceil(64)];
+        for id in 0..num_node {
+            remaining[id / 64] |= 1 << (id % 64);
+        }
+        self.num_branch = 0;
+        self.is_exhausted = false;
+        self.maximum(remaining).into_iter().collect()
+    }
+
+    fn degree(&self, id: usize, remaining: &Bits) -> usize {
+        count_common(&self.adjacency[id], remaining)
+    }
+
+    fn take(&self, remaining: &mut Bits, chosen: &mut Vec<usize>, id: usize) {
+        chosen.push(id);
+        remove(remaining, id);
+        for (word, n_word) in remaining.iter_mut().zip(self.adjacency[id].iter()) {
+            *word &= !n_word;
+        }
+    }
+
+    // maximum independent set of the nodes in remaining, min degree greedy is the first incumbent
+    fn maximum(&mut self, remaining: Bits) -> Vec<usize> {
+        let mut best: Vec<usize> = vec![];
+        let mut left = remaining.clone();
+        while let Some(id) = ones(&left).into_iter().min_by_key(|id| self.degree(*id, &left)) {
+            self.take(&mut left, &mut best, id);
+        }
+        self.search(remaining, &mut vec![], &mut best);
+        best
+    }
+
+    // closed neighborhood of u within the remaining graph is included in the one of v
+    fn is_dominated(&self, u: usize, v: usize, remaining: &Bits) -> bool {
+        for (w, word) in remaining.iter().enumerate() {
+            let mut outside = self.adjacency[u][w] & word & !self.adjacency[v][w];
+            if v / 64 == w {
+                outside &= !(1 << (v % 64));
+            }
+            if outside != 0 {
+                return false;
+            }
+        }
+        true
+    }
+
+    // takes isolated nodes and drops dominating ones, some maximum independent set of the
+    // remaining graph survives both
+    fn reduce(&self, remaining: &mut Bits, chosen: &mut Vec<usize>) {
+        let mut is_changed = true;
+        while is_changed {
+            is_changed = false;
+            for id in ones(remaining) {
+                if !contains(remaining, id) {
+                    continue;
+                }
+                if self.degree(id, remaining) == 0 {
+                    self.take(remaining, chosen, id);
+                    is_changed = true;
+                    continue;
+                }
+                let neighbors: Vec<usize> = ones(&self.adjacency[id]).into_iter()
+                    .filter(|n| contains(remaining, *n))
+                    .collect();
+                for n_id in neighbors {
+                    if self.is_dominated(id, n_id, remaining) {
+                        remove(remaining, n_id);
+                        is_changed = true;
+                    }
+                }
+            }
+        }
+    }
+
+    fn components(&self, remaining: &Bits) -> Vec<Bits> {
+        let mut unvisited = remaining.clone();
+        let mut components: Vec<Bits> = vec![];
+        while let Some(root) = ones(&unvisited).first().cloned() {
+            let mut component: Bits = vec![0; remaining.len()];
+            let mut stack = vec![root];
+            remove(&mut unvisited, root);
+            while let Some(id) = stack.pop() {
+                component[id / 64] |= 1 << (id % 64);
+                let next: Vec<usize> = ones(&self.adjacency[id]).into_iter()
+                    .filter(|n| contains(&unvisited, *n))
+                    .collect();
+                for n_id in next {
+                    remove(&mut unvisited, n_id);
+                    stack.push(n_id);
+                }
+            }
+            components.push(component);
+        }
+        components
+    }
+
+    // the smaller of a greedy clique cover size and nodes minus edges over max degree
+    fn upper_bound(&self, remaining: &Bits) -> usize {
+        let mut ids = ones(remaining);
+        ids.sort_by_key(|id| self.degree(*id, remaining));
+        let mut common: Vec<Bits> = vec![]; //nodes adjacent to every member of each clique
+        for id in ids.iter() {
+            match common.iter_mut().find(|c| contains(c, *id)) {
+                Some(clique) => {
+                    for (word, n_word) in clique.iter_mut().zip(self.adjacency[*id].iter()) {
+                        *word &= n_word;
+                    }
+                },
+                None => common.push(self.adjacency[*id].clone()),
+            }
+        }
+        let degrees: Vec<usize> = ids.iter().map(|id| self.degree(*id, remaining)).collect();
+        let max_degree = degrees.iter().cloned().max().unwrap_or(0).max(1);
+        let num_edge: usize = degrees.iter().sum::<usize>() / 2;
+        common.len().min(ids.len() - num_edge.div_ceil(max_degree))
+    }
+
+    fn search(&mut self, mut remaining: Bits, chosen: &mut Vec<usize>, best: &mut Vec<usize>) {
+        if self.max_branch.is_some_and(|max_branch| self.num_branch >= max_branch) {
+            self.is_exhausted = true;
+            return;
+        }
+        self.num_branch += 1;
+        let num_chosen = chosen.len();
+        self.reduce(&mut remaining, chosen);
+        let ids = ones(&remaining);
+        if ids.is_empty() || chosen.len() + self.upper_bound(&remaining) <= best.len() {
+            if chosen.len() > best.len() {
+                *best = chosen.clone();
+            }
+            chosen.truncate(num_chosen);
+            return;
+        }
+
+        let components = self.components(&remaining);
+        if components.len() > 1 {
+            for component in components {
+                let part = self.maximum(component);
+                chosen.extend(part);
+            }
+            if chosen.len() > best.len() {
+                *best = chosen.clone();
+            }
+            chosen.truncate(num_chosen);
+            return;
+        }
+
+        let pivot = *ids.iter()
+            .max_by_key(|id| self.degree(**id, &remaining))
+            .unwrap();
+        let mut with_pivot = remaining.clone();
+        self.take(&mut with_pivot, chosen, pivot);
+        self.search(with_pivot, chosen, best);
+        chosen.pop();
+
+        remove(&mut remaining, pivot);
+        self.search(remaining, chosen, best);
+        chosen.truncate(num_chosen);
+    }
+}
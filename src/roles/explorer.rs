@@ -0,0 +1,435 @@
+use super::node::{Algorithm, is_commuting};
+use super::scheduler::{Scheduler, Policy};
+use super::desire::{DesireConfig};
+use super::tracer::{TraceEvent, Endpoint, Payload};
+use super::verifier::{Verifier};
+use std::cell::{Cell};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::panic::{self, AssertUnwindSafe, PanicHookInfo};
+use std::sync::{Arc, Mutex};
+use std::sync::mpsc::{Sender};
+
+// an execution gives up after this many deliveries
+const MAX_STEP: usize = 100000;
+
+pub type Link = (Endpoint, Endpoint); //sender, receiver
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Anomaly {
+    Deadlock, //nothing to deliver and the coordinator cannot move on
+    Panic(String),
+    WrongMis(usize), //number of violations
+    StepLimit,
+}
+
+impl Anomaly {
+    pub fn describe(&self) -> String {
+        match self {
+            Anomaly::Deadlock => "deadlock".to_string(),
+            Anomaly::Panic(message) => format!("panic \"{}\"", message),
+            Anomaly::WrongMis(num_violation) => format!("wrong mis with {} violations", num_violation),
+            Anomaly::StepLimit => format!("no end after {} deliveries", MAX_STEP),
+        }
+    }
+}
+
+// the deliveries leading to the first anomaly of its kind
+#[derive(Clone, Debug)]
+pub struct Counterexample {
+    pub anomaly: Anomaly,
+    pub schedule: Vec<Link>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Exploration {
+    pub num_execution: usize,
+    pub num_redundant: usize, //stopped early since every order left was covered elsewhere
+    pub num_merged: usize, //stopped at the end of a stage in a state explored before
+    pub num_state: usize, //distinct states at the end of a stage
+    pub is_complete: bool, //every delivery order was covered
+    pub max_depth: usize,
+    pub num_deadlock: usize,
+    pub num_panic: usize,
+    pub num_wrong: usize,
+    pub num_step_limit: usize,
+    pub num_lost: usize, //executions delivering a message to a node that had left
+    pub results: BTreeMap<Vec<usize>, usize>, //sorted mis, executions ending with it
+    pub counterexamples: Vec<Counterexample>,
+}
+
+enum Ending {
+    Finished(Vec<usize>), //sorted mis
+    Asleep, //every pending message sleeps
+    Merged,
+}
+
+// a state of the current execution: what could be delivered next, which of it was,
+// which still has to be and which leads to executions covered elsewhere
+struct Frame {
+    enabled: Vec<Link>,
+    kinds: Vec<&'static str>, //of the next message of every enabled link
+    chosen: Link,
+    backtrack: HashSet<Link>,
+    done: HashSet<Link>,
+    sleep: HashSet<Link>,
+}
+
+impl Frame {
+    fn kind(&self, link: &Link) -> &'static str {
+        self.kinds[self.enabled.iter().position(|l| l == link).expect("a link enabled at the frame")]
+    }
+}
+
+// model checking of the message passing protocol with fixed random choices: executions
+// are re-run from scratch on the scheduler, each one following the delivery order of the
+// last one up to a state where another order still has to be tried.
+// deliveries to different receivers commute, and so do deliveries to a node from
+// different neighbors whose kinds commute there, so only the order of the other
+// messages at each receiver matters, and dynamic partial order reduction (Flanagan and
+// Godefroid) only tries another order at a receiver when a conflicting message could
+// have arrived there earlier, which the deliveries known to happen before each one tell,
+// while sleep sets skip the orders only differing from an explored one by swapping
+// deliveries that commute.
+// the coordinator only counts the messages of a stage and collects results, so its
+// deliveries from different nodes commute too, up to the order of its lists, and each of
+// its links counts as a receiver of its own, what it sends depends on all of them.
+// a stage ends once the coordinator heard from every node, which each node only does
+// after hearing from all its neighbors, so every delivery of the stage happened before
+// any of the next one and no two race across stages: an execution reaching the end of a
+// stage in a state explored before stops there
+pub struct Explorer {
+    pub graph: Vec<Vec<usize>>,
+    pub weights: Vec<f32>,
+    pub algorithm: Algorithm,
+    pub desire: DesireConfig,
+    pub seed: u64,
+}
+
+impl Explorer {
+    pub fn new(graph: &[Vec<usize>], weights: &[f32], algorithm: Algorithm, seed: u64) -> Explorer {
+        Explorer {
+            graph: graph.to_vec(),
+            weights: weights.to_vec(),
+            algorithm,
+            desire: DesireConfig::default(),
+            seed,
+        }
+    }
+
+    pub fn set_desire(&mut self, desire: DesireConfig) {
+        self.desire = desire;
+    }
+
+    // deliveries with different indices commute
+    fn receiver_index(&self, link: &Link) -> usize {
+        match link {
+            (_, Endpoint::Node(id)) => *id,
+            (Endpoint::Node(id), Endpoint::Coordinator) => self.graph.len() + id,
+            (Endpoint::Coordinator, Endpoint::Coordinator) => unreachable!(),
+        }
+    }
+
+    // the messages of one link keep their order, messages from different links to a node
+    // may commute
+    fn is_dependent(&self, (link, kind): (&Link, &str), (other, other_kind): (&Link, &str)) -> bool {
+        if self.receiver_index(link) != self.receiver_index(other) {
+            return false;
+        }
+        match link.1 {
+            Endpoint::Node(_) if link != other => !is_commuting(kind, other_kind),
+            _ => true,
+        }
+    }
+
+    // panics of the nodes are caught, the default hook would print every one of them
+    pub fn explore(&self, max_execution: usize) -> Exploration {
+        let mut exploration = Exploration::default();
+        let mut stack: Vec<Frame> = vec![];
+        let mut visited: HashSet<String> = HashSet::new();
+        let _silence = SilentPanics::new();
+        loop {
+            let mut schedule: Vec<Link> = vec![];
+            let mut num_lost = 0;
+            let outcome = panic::catch_unwind(AssertUnwindSafe(|| self.execute(&mut stack, &mut visited, &mut schedule, &mut num_lost)))
+                .unwrap_or_else(|payload| {
+                    let message = match (payload.downcast_ref::<&str>(), payload.downcast_ref::<String>()) {
+                        (Some(message), _) => message.to_string(),
+                        (_, Some(message)) => message.clone(),
+                        _ => "unknown".to_string(),
+                    };
+                    Err(Anomaly::Panic(message))
+                });
+            exploration.num_execution += 1;
+            exploration.max_depth = exploration.max_depth.max(schedule.len());
+            if num_lost > 0 {
+                exploration.num_lost += 1;
+            }
+            match outcome {
+                Ok(Ending::Finished(mis)) => *exploration.results.entry(mis).or_insert(0) += 1,
+                Ok(Ending::Asleep) => exploration.num_redundant += 1,
+                Ok(Ending::Merged) => exploration.num_merged += 1,
+                Err(anomaly) => {
+                    match anomaly {
+                        Anomaly::Deadlock => exploration.num_deadlock += 1,
+                        Anomaly::Panic(_) => exploration.num_panic += 1,
+                        Anomaly::WrongMis(_) => exploration.num_wrong += 1,
+                        Anomaly::StepLimit => exploration.num_step_limit += 1,
+                    }
+                    let is_new = !exploration.counterexamples.iter()
+                        .any(|c| std::mem::discriminant(&c.anomaly) == std::mem::discriminant(&anomaly));
+                    if is_new {
+                        exploration.counterexamples.push(Counterexample { anomaly, schedule });
+                    }
+                },
+            }
+            if !Explorer::backtrack(&mut stack) {
+                exploration.is_complete = true;
+                break;
+            }
+            if exploration.num_execution >= max_execution {
+                break;
+            }
+        }
+        exploration.num_state = visited.len();
+        exploration
+    }
+
+    // moves to the deepest state with an order left to try, false when there is none
+    fn backtrack(stack: &mut Vec<Frame>) -> bool {
+        while let Some(frame) = stack.last_mut() {
+            let next = frame.enabled.iter()
+                .find(|link| frame.backtrack.contains(link) && !frame.done.contains(link) && !frame.sleep.contains(link))
+                .cloned();
+            if let Some(link) = next {
+                frame.chosen = link;
+                frame.done.insert(link);
+                return true;
+            }
+            stack.pop();
+        }
+        false
+    }
+
+    // follows the chosen deliveries of the stack, then the oldest pending message out of
+    // the sleep set, and records where another order has to be tried
+    fn execute(
+        &self,
+        stack: &mut Vec<Frame>,
+        visited: &mut HashSet<String>,
+        schedule: &mut Vec<Link>,
+        num_lost: &mut usize,
+    ) -> Result<Ending, Anomaly> {
+        let num_clock = 2 * self.graph.len();
+        let mut scheduler = Scheduler::new(&self.graph, &self.weights, self.algorithm, self.seed, Policy::Fifo);
+        scheduler.set_desire(self.desire);
+        let mut clocks: Vec<Vec<usize>> = vec![]; //of every delivery
+        let mut deliveries: Vec<Vec<(usize, Link, &'static str)>> = vec![vec![]; num_clock]; //depth, link and kind at each receiver
+        let mut sent_clock: HashMap<usize, Vec<usize>> = HashMap::new(); //message seq, clock of its sending
+        let mut coordinator_clock: Vec<usize> = vec![0; num_clock]; //of every delivery to the coordinator
+        scheduler.settle();
+        loop {
+            let depth = schedule.len();
+            if scheduler.coordinator.is_finished() {
+                scheduler.coordinator.finish();
+                *num_lost = scheduler.num_lost;
+                let mis = scheduler.coordinator.get_sorted_mis();
+                let violations = Verifier::new(&mis.iter().cloned().collect(), &self.graph).find_violations();
+                if !violations.is_empty() {
+                    return Err(Anomaly::WrongMis(violations.len()));
+                }
+                return Ok(Ending::Finished(mis));
+            }
+            *num_lost = scheduler.num_lost;
+            if depth >= MAX_STEP {
+                return Err(Anomaly::StepLimit);
+            }
+            let deliverable = scheduler.deliverable();
+            if deliverable.is_empty() {
+                return Err(Anomaly::Deadlock);
+            }
+            let enabled: Vec<Link> = deliverable.iter().map(|index| scheduler.link(*index)).collect();
+            let kinds: Vec<&'static str> = deliverable.iter().map(|index| kind(&scheduler.in_flight[*index].payload)).collect();
+            // a new state: the next message of a link races with the last delivery at its
+            // receiver it does not commute with unless that delivery happened before it was sent
+            if depth == stack.len() {
+                for ((index, link), kind) in deliverable.iter().zip(enabled.iter()).zip(kinds.iter()) {
+                    let receiver = self.receiver_index(link);
+                    let last = match deliveries[receiver].iter().rev().find(|(_, other, other_kind)| self.is_dependent((link, kind), (other, other_kind))) {
+                        Some((last, _, _)) => *last,
+                        None => continue,
+                    };
+                    let is_before = sent_clock.get(&scheduler.in_flight[*index].seq).is_some_and(|clock| clock[receiver] > last);
+                    if !is_before {
+                        let frame = &mut stack[last];
+                        if frame.enabled.contains(link) {
+                            frame.backtrack.insert(*link);
+                        } else {
+                            frame.backtrack.extend(frame.enabled.iter().cloned());
+                        }
+                    }
+                }
+                // the deliveries explored before at the parent state still sleep unless
+                // they do not commute with the parent delivery
+                let sleep: HashSet<Link> = match stack.last() {
+                    Some(parent) => parent.sleep.iter().chain(parent.done.iter())
+                        .filter(|link| !self.is_dependent((link, parent.kind(link)), (&parent.chosen, parent.kind(&parent.chosen))))
+                        .cloned()
+                        .collect(),
+                    None => HashSet::new(),
+                };
+                let chosen = match enabled.iter().find(|link| !sleep.contains(link)) {
+                    Some(chosen) => *chosen,
+                    None => return Ok(Ending::Asleep),
+                };
+                stack.push(Frame {
+                    enabled: enabled.clone(),
+                    kinds: kinds.clone(),
+                    chosen,
+                    backtrack: [chosen].iter().cloned().collect(),
+                    done: [chosen].iter().cloned().collect(),
+                    sleep,
+                });
+            }
+            let link = stack[depth].chosen;
+            let position = enabled.iter().position(|l| *l == link).expect("the execution diverges from the explored one");
+            let index = deliverable[position];
+            // the deliveries known to have happened before this one: those before the
+            // sending of the message and those before the last delivery at the receiver
+            let receiver = self.receiver_index(&link);
+            let kind = kinds[position];
+            let mut clock = sent_clock.remove(&scheduler.in_flight[index].seq).unwrap_or_else(|| vec![0; num_clock]);
+            if let Some((last, _, _)) = deliveries[receiver].last() {
+                join(&mut clock, &clocks[*last]);
+            }
+            clock[receiver] = depth + 1;
+            let clock_of_sent = match link.1 {
+                Endpoint::Coordinator => {
+                    join(&mut coordinator_clock, &clock);
+                    coordinator_clock.clone()
+                },
+                Endpoint::Node(_) => clock.clone(),
+            };
+
+            let num_seq = scheduler.num_seq;
+            let stage = (scheduler.coordinator.round, scheduler.coordinator.stage);
+            schedule.push(link);
+            scheduler.deliver(index);
+            scheduler.settle();
+            if (scheduler.coordinator.round, scheduler.coordinator.stage) != stage && !scheduler.coordinator.is_finished()
+                && !visited.insert(self.fingerprint(&scheduler)) {
+                *num_lost = scheduler.num_lost;
+                return Ok(Ending::Merged);
+            }
+            for seq in num_seq..scheduler.num_seq {
+                sent_clock.insert(seq, clock_of_sent.clone());
+            }
+            clocks.push(clock);
+            deliveries[receiver].push((depth, link, kind));
+        }
+    }
+
+    // the state at the end of a stage: the nodes, the results and the messages of the next one
+    fn fingerprint(&self, scheduler: &Scheduler) -> String {
+        let coordinator = &scheduler.coordinator;
+        let mut results: Vec<(usize, bool)> = coordinator.result_list.iter().map(|r| (r.id, r.is_in_mis)).collect();
+        results.sort();
+        let nodes: Vec<String> = scheduler.nodes.iter().map(|node| match node {
+            Some(node) => node.fingerprint(),
+            None => "left".to_string(),
+        }).collect();
+        let in_flight: Vec<String> = scheduler.in_flight.iter().map(|m| format!("{:?}", (m.from, m.to, &m.payload))).collect();
+        format!("{} {:?} {:?} {:?} {} {}", coordinator.round, coordinator.stage, results, coordinator.is_active, in_flight.join(","), nodes.join(";"))
+    }
+
+    // re-runs one schedule, recording it when a tracer is given, up to the panic if any
+    pub fn rerun(&self, schedule: &[Link], tracer: Option<Sender<TraceEvent>>) {
+        let _silence = SilentPanics::new();
+        let _ = panic::catch_unwind(AssertUnwindSafe(|| self.follow(schedule, tracer)));
+    }
+
+    fn follow(&self, schedule: &[Link], tracer: Option<Sender<TraceEvent>>) {
+        let mut scheduler = Scheduler::new(&self.graph, &self.weights, self.algorithm, self.seed, Policy::Fifo);
+        scheduler.set_desire(self.desire);
+        if let Some(tracer) = tracer {
+            scheduler.set_tracer(tracer);
+        }
+        scheduler.settle();
+        for link in schedule.iter() {
+            let index = match scheduler.deliverable().into_iter().find(|index| scheduler.link(*index) == *link) {
+                Some(index) => index,
+                None => return,
+            };
+            scheduler.deliver(index);
+            scheduler.settle();
+            if scheduler.coordinator.is_finished() {
+                scheduler.coordinator.finish();
+                return;
+            }
+        }
+    }
+}
+
+fn kind(payload: &Payload) -> &'static str {
+    match payload {
+        Payload::Node(message) => message.kind(),
+        Payload::Central(message) => message.kind(),
+    }
+}
+
+fn join(clock: &mut [usize], other: &[usize]) {
+    for (c, o) in clock.iter_mut().zip(other.iter()) {
+        *c = (*c).max(*o);
+    }
+}
+
+type PanicHook = Box<dyn Fn(&PanicHookInfo<'_>) + Sync + Send + 'static>;
+
+// the hook found when the first guard was made, and the number of live guards
+static SAVED_HOOK: Mutex<(usize, Option<Arc<PanicHook>>)> = Mutex::new((0, None));
+
+thread_local! {
+    static IS_SILENT: Cell<bool> = const { Cell::new(false) };
+}
+
+// while a guard lives, panics of its own thread are not reported, other threads still go
+// through the hook found before, which is put back once the last guard is dropped
+struct SilentPanics {
+    was_silent: bool,
+}
+
+impl SilentPanics {
+    fn new() -> SilentPanics {
+        let mut saved = SAVED_HOOK.lock().unwrap_or_else(|e| e.into_inner());
+        if saved.1.is_none() {
+            let hook: Arc<PanicHook> = Arc::new(panic::take_hook());
+            saved.1 = Some(hook.clone());
+            panic::set_hook(Box::new(move |info| {
+                if !IS_SILENT.with(|is_silent| is_silent.get()) {
+                    hook(info);
+                }
+            }));
+        }
+        saved.0 += 1;
+        SilentPanics { was_silent: IS_SILENT.with(|is_silent| is_silent.replace(true)) }
+    }
+}
+
+impl Drop for SilentPanics {
+    fn drop(&mut self) {
+        IS_SILENT.with(|is_silent| is_silent.set(self.was_silent));
+        let mut saved = SAVED_HOOK.lock().unwrap_or_else(|e| e.into_inner());
+        saved.0 -= 1;
+        // the hook cannot be changed while this thread panics, the wrapper stays then
+        if saved.0 > 0 || std::thread::panicking() {
+            return;
+        }
+        if let Some(hook) = saved.1.take() {
+            // dropping the wrapper leaves the saved hook as the only owner
+            drop(panic::take_hook());
+            match Arc::try_unwrap(hook) {
+                Ok(hook) => panic::set_hook(hook),
+                Err(hook) => panic::set_hook(Box::new(move |info| hook(info))),
+            }
+        }
+    }
+}
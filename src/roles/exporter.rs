@@ -0,0 +1,84 @@
+use super::verifier::{Violation};
+use std::collections::{HashSet};
+use std::fs::File;
+use std::io::{BufWriter, Write, Result};
+
+// writes a solved graph as Graphviz DOT: mis nodes are filled, every node shows the
+// round in which it decided, and verifier violations are drawn in red
+pub struct DotExporter {
+    pub graph: Vec<Vec<usize>>,
+    pub mis: HashSet<usize>,
+    pub decide_round: Vec<Option<usize>>,
+    pub violations: Vec<Violation>,
+}
+
+impl DotExporter {
+    pub fn new(
+        graph: &[Vec<usize>],
+        mis: &HashSet<usize>,
+        decide_round: &[Option<usize>],
+        violations: &[Violation],
+    ) -> DotExporter {
+        DotExporter {
+            graph: graph.to_vec(),
+            mis: mis.clone(),
+            decide_round: decide_round.to_vec(),
+            violations: violations.to_vec(),
+        }
+    }
+
+    fn edges(&self) -> Vec<(usize, usize)> {
+        let mut edge_set: HashSet<(usize, usize)> = HashSet::new();
+        for i in 0..self.graph.len() {
+            for j in self.graph[i].iter() {
+                edge_set.insert((i.min(*j), i.max(*j)));
+            }
+        }
+        let mut edges: Vec<(usize, usize)> = edge_set.into_iter().collect();
+        edges.sort();
+        edges
+    }
+
+    pub fn to_dot(&self) -> String {
+        let mut bad_edges: HashSet<(usize, usize)> = HashSet::new();
+        let mut uncovered: HashSet<usize> = HashSet::new();
+        for violation in self.violations.iter() {
+            match violation {
+                Violation::Independence(i, j) => { bad_edges.insert((*i.min(j), *i.max(j))); },
+                Violation::Maximality(i) => { uncovered.insert(*i); },
+            }
+        }
+
+        let mut dot = String::from("graph mis {\n");
+        dot.push_str("    node [shape=circle, style=filled, fillcolor=white];\n");
+        for i in 0..self.graph.len() {
+            let round = match self.decide_round.get(i) {
+                Some(Some(round)) => format!("r{}", round),
+                _ => "undecided".to_string(),
+            };
+            let mut attrs = vec![format!("label=\"{}\\n{}\"", i, round)];
+            if self.mis.contains(&i) {
+                attrs.push("fillcolor=lightblue".to_string());
+            }
+            if uncovered.contains(&i) {
+                attrs.push("color=red, penwidth=3, fontcolor=red".to_string());
+            }
+            dot.push_str(&format!("    {} [{}];\n", i, attrs.join(", ")));
+        }
+        for (i, j) in self.edges() {
+            if bad_edges.contains(&(i, j)) {
+                dot.push_str(&format!("    {} -- {} [color=red, penwidth=3];\n", i, j));
+            } else {
+                dot.push_str(&format!("    {} -- {};\n", i, j));
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    pub fn store(&self, filename: &str) -> Result<()> {
+        let mut f = BufWriter::new(File::create(filename)?);
+        f.write_all(self.to_dot().as_bytes())?;
+        f.flush()
+    }
+}
@@ -0,0 +1,30 @@
+// an adjacency file may list an edge at one end or at both, and a node may list itself,
+// every model works on the symmetric graph without self loops
+
+// every edge once, smaller id first, in increasing order
+pub fn undirected_edges(graph: &[Vec<usize>]) -> Vec<(usize, usize)> {
+    let mut edges: Vec<(usize, usize)> = vec![];
+    for (i, dsts) in graph.iter().enumerate() {
+        for j in dsts.iter() {
+            if i != *j {
+                edges.push((i.min(*j), i.max(*j)));
+            }
+        }
+    }
+    edges.sort();
+    edges.dedup();
+    edges
+}
+
+// the neighbors of every node, in increasing order
+pub fn undirected_neighbors(graph: &[Vec<usize>]) -> Vec<Vec<usize>> {
+    let mut neighbors: Vec<Vec<usize>> = vec![vec![]; graph.len()];
+    for (i, j) in undirected_edges(graph) {
+        neighbors[i].push(j);
+        neighbors[j].push(i);
+    }
+    for n in neighbors.iter_mut() {
+        n.sort();
+    }
+    neighbors
+}
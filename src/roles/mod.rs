@@ -2,3 +2,25 @@ pub mod node;
 pub mod coordinator;
 pub mod verifier;
 pub mod creater;
+pub mod tracer;
+pub mod replayer;
+pub mod exporter;
+pub mod animator;
+pub mod baseline;
+pub mod exact;
+pub mod beeping;
+pub mod deterministic;
+pub mod ruling;
+pub mod shattering;
+pub mod dynamic;
+pub mod stream;
+pub mod stabilizing;
+pub mod byzantine;
+pub mod scheduler;
+pub mod explorer;
+pub mod shrinker;
+pub mod checkpoint;
+pub mod desire;
+pub mod sweep;
+pub mod graph;
+pub mod runner;
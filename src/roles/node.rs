@@ -1,42 +1,101 @@
 extern crate rand;
-use rand::{Rng, thread_rng};
-
-use std::sync::mpsc::{self, channel, Sender, Receiver, TryRecvError};
-use std::collections::{HashMap, HashSet};
-use std::{thread, time};
+use rand::{Rng, SeedableRng, thread_rng};
+use rand::prng::XorShiftRng;
+use serde::{Serialize, Deserialize};
+use super::tracer::{TraceEvent, Endpoint, Payload};
+use super::coordinator::{Stage};
+use super::byzantine::{Strategy, FALSE_DESIRE_LEVEL};
+use super::checkpoint::{NodeState};
+use super::desire::{DesireConfig};
+use crate::log_event;
+use crate::logger::{Level};
+
+use std::sync::mpsc::{channel, Sender, Receiver};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::thread::JoinHandle;
 
  
 
-#[derive(Debug, Clone)]
+// how a node decides to be marked in a round
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Algorithm {
+    Luby, //marked with probability desire level
+    Metivier, //random bits are exchanged until a node beats all its neighbors
+    Coloring, //(degree + 1)-coloring instead of mis, a node keeps a random free color no neighbor tries
+    Matching, //maximal matching instead of mis, Israeli-Itai proposals
+    Weighted, //desire level scaled by the weight relative to the heaviest neighbor, the heavier marked node wins
+}
+
+impl Algorithm {
+    pub fn parse(algorithm: &str) -> Option<Algorithm> {
+        match algorithm.to_lowercase().as_str() {
+            "luby" => Some(Algorithm::Luby),
+            "metivier" => Some(Algorithm::Metivier),
+            "coloring" => Some(Algorithm::Coloring),
+            "matching" => Some(Algorithm::Matching),
+            "weighted" => Some(Algorithm::Weighted),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Algorithm::Luby => "luby",
+            Algorithm::Metivier => "metivier",
+            Algorithm::Coloring => "coloring",
+            Algorithm::Matching => "matching",
+            Algorithm::Weighted => "weighted",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Message {
     Request((usize, usize)), //neighbor id, round
+    Bit((usize, usize, bool)), //neighbor id, round, random bit
+    TryColor((usize, usize, usize)), //neighbor id, round, color
+    Propose((usize, usize, bool)), //neighbor id, round, proposes to the receiver
+    Accept((usize, usize, bool)), //neighbor id, round, accepts the proposal of the receiver
+    Choose((usize, usize, bool)), //neighbor id, round, chooses the edge to the receiver
     Response(Data),
     JoinedMIS((bool, usize)),
     RemoveNeighbors(HashSet<usize>),
     Start(usize),
     StartRound2,
+    Checkpoint, //the coordinator asks for the state of the node between two rounds
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum CentralMessage {
     Step(usize), //id
     Finish(NodeResult), 
-    Round1Complete,
+    Round1Complete((usize, bool, usize)), //id, is marked, bits sent in the round
     ReconfigComplete(ReconfigResult), //leaving isolated node id
+    State(NodeState), //for the checkpoint
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct ReconfigResult {
     pub id: usize,
     pub is_leaving: bool,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct NodeResult {
     pub id: usize,
     pub is_in_mis: bool,
     pub nodes_to_remove: Vec<usize>,
+    pub color: Option<usize>,
+    pub partner: Option<usize>,
+}
+
+// progress of a matching round inside the first stage
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum MatchStep {
+    Idle,
+    Propose, //proposal sent, waiting for the proposals of every neighbor
+    Accept, //answers sent, waiting for the answers of every neighbor
+    Choose, //edge chosen, waiting for the choice of every proposal edge
 }
 
 
@@ -47,16 +106,113 @@ pub struct Node {
     pub round: usize,
     pub central_sender: Sender<CentralMessage>,
     pub desire_level: f32,
+    pub desire: DesireConfig,
+    pub weight: f32,
     pub is_get_marked: bool,
+    pub mark_round: Option<usize>, //round of is_get_marked
+    pub early_requests: Vec<usize>, //neighbors asking before this node marked
     pub num_response: usize,
     pub is_in_mis: bool,
+    pub num_neighbor_joined: usize,
+    pub is_deciding: bool, //StartRound2 arrived, the last JoinedMIS of the neighbors ends the round
+    pub any_neighbor_joined: bool,
+    pub rng: XorShiftRng,
+    pub tracer: Option<Sender<TraceEvent>>,
+    pub algorithm: Algorithm,
+    pub strategy: Option<Strategy>, //lies to the neighbors when set
+    pub tied: HashSet<usize>, //neighbors with the same bits so far
+    pub bit: bool,
+    pub is_comparing: bool,
+    pub num_bit: usize,
+    pub palette: usize, //colors 0..palette, degree + 1
+    pub color: Option<usize>,
+    pub tentative_color: usize,
+    pub used_colors: HashSet<usize>, //kept by neighbors
+    pub match_step: MatchStep,
+    pub proposee: Option<usize>,
+    pub match_edges: Vec<usize>, //neighbors joined by an accepted proposal
+    pub match_choice: Option<usize>,
+    pub partner: Option<usize>,
+    pub num_neighbor_matched: usize,
+}
+
+// the seed of the stream of one node, shared by the nodes of every model
+pub fn node_seed(seed: u64, id: usize) -> u64 {
+    seed ^ (id as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15)
+}
+
+// messages of these kinds from two different neighbors leave a node in the same state
+// and make it send the same messages on every link in either order: requests are
+// answered with the node's own state, responses and joins only touch the counters and
+// the context of their sender, and a request before Start waits to be answered at Start
+// right after the node's own request on that link
+pub fn is_commuting(kind: &str, other: &str) -> bool {
+    matches!((kind.min(other), kind.max(other)),
+        ("Request", "Request") | ("Response", "Response") | ("Request", "Response") | ("Request", "Start") | ("JoinedMIS", "JoinedMIS"))
 }
 
-#[derive(Copy, Debug, Clone)]
+impl Message {
+    // the node that sent the message, None when it comes from the coordinator
+    pub fn sender_id(&self) -> Option<usize> {
+        match self {
+            Message::Request((neighbor_id, _)) => Some(*neighbor_id),
+            Message::Bit((neighbor_id, _, _)) => Some(*neighbor_id),
+            Message::TryColor((neighbor_id, _, _)) => Some(*neighbor_id),
+            Message::Propose((neighbor_id, _, _)) => Some(*neighbor_id),
+            Message::Accept((neighbor_id, _, _)) => Some(*neighbor_id),
+            Message::Choose((neighbor_id, _, _)) => Some(*neighbor_id),
+            Message::Response(data) => Some(data.sender_id),
+            Message::JoinedMIS((_, neighbor_id)) => Some(*neighbor_id),
+            Message::RemoveNeighbors(_) | Message::Start(_) | Message::StartRound2 | Message::Checkpoint => None,
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Message::Request(_) => "Request",
+            Message::Bit(_) => "Bit",
+            Message::TryColor(_) => "TryColor",
+            Message::Propose(_) => "Propose",
+            Message::Accept(_) => "Accept",
+            Message::Choose(_) => "Choose",
+            Message::Response(_) => "Response",
+            Message::JoinedMIS(_) => "JoinedMIS",
+            Message::RemoveNeighbors(_) => "RemoveNeighbors",
+            Message::Start(_) => "Start",
+            Message::StartRound2 => "StartRound2",
+            Message::Checkpoint => "Checkpoint",
+        }
+    }
+}
+
+impl CentralMessage {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            CentralMessage::Step(_) => "Step",
+            CentralMessage::Finish(_) => "Finish",
+            CentralMessage::Round1Complete(_) => "Round1Complete",
+            CentralMessage::ReconfigComplete(_) => "ReconfigComplete",
+            CentralMessage::State(_) => "State",
+        }
+    }
+
+    pub fn sender_id(&self) -> usize {
+        match self {
+            CentralMessage::Step(id) => *id,
+            CentralMessage::Finish(result) => result.id,
+            CentralMessage::Round1Complete((id, _, _)) => *id,
+            CentralMessage::ReconfigComplete(result) => result.id,
+            CentralMessage::State(state) => state.id,
+        }
+    }
+}
+
+#[derive(Copy, Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Data {
     sender_id: usize,
     round: usize,
     desire_level: f32,
+    weight: f32,
     is_get_marked: bool,
 }
 
@@ -66,28 +222,38 @@ pub struct NeighborContext {
     pub sender: Sender<Message>,
     pub is_marked: bool,
     pub desire_level: f32,
-    
+    pub weight: f32, //0 until the first response
+    pub bits: VecDeque<bool>, //received but not compared yet
+    pub color: Option<usize>, //tried in the current round
+    pub proposal: Option<bool>, //received in the current round
+    pub acceptance: Option<bool>,
+    pub choice: Option<bool>,
 }
 
 impl NeighborContext {
-    pub fn new(sender: Sender<Message>) -> NeighborContext {
+    // every node starts from the same configured desire level, the neighbor's included
+    pub fn new(sender: Sender<Message>, desire_level: f32) -> NeighborContext {
         NeighborContext {
             round: 0,
-            sender: sender,
+            sender,
             is_marked: false,
-            desire_level: 0.5,
+            desire_level,
+            weight: 0.0,
+            bits: VecDeque::new(),
+            color: None,
+            proposal: None,
+            acceptance: None,
+            choice: None,
         }
     }
     pub fn send(&mut self, message: Message) -> bool {
-        match self.sender.send(message) {
-            Ok(()) => return true, 
-            Err(e) => return false,
-        }; 
+        self.sender.send(message).is_ok()
     }
 
     pub fn update(&mut self, data: Data) {
         self.round = data.round;
         self.desire_level = data.desire_level;
+        self.weight = data.weight;
         self.is_marked = data.is_get_marked;
     }
 }
@@ -100,24 +266,105 @@ impl Node {
     ) -> (Node, Sender<Message>) {
         let (tx, rx) = channel();
         let node = Node {
-            id: id,
+            id,
             neighbors: HashMap::new(),
             receiver: Some(rx),
             round: 0,
-            central_sender: central_sender,
+            central_sender,
             desire_level: 0.5,
+            desire: DesireConfig::default(),
+            weight: 1.0,
             is_get_marked: false,
+            mark_round: None,
+            early_requests: vec![],
             num_response: 0,
             is_in_mis: false,
+            num_neighbor_joined: 0,
+            is_deciding: false,
+            any_neighbor_joined: false,
+            rng: XorShiftRng::from_rng(thread_rng()).expect("unable to seed node rng"),
+            tracer: None,
+            algorithm: Algorithm::Luby,
+            strategy: None,
+            tied: HashSet::new(),
+            bit: false,
+            is_comparing: false,
+            num_bit: 0,
+            palette: 0,
+            color: None,
+            tentative_color: 0,
+            used_colors: HashSet::new(),
+            match_step: MatchStep::Idle,
+            proposee: None,
+            match_edges: vec![],
+            match_choice: None,
+            partner: None,
+            num_neighbor_matched: 0,
         };
         (node, tx)
     }
 
+    // every node draws from its own stream, so a seeded run makes the same
+    // random choices whatever the thread interleaving
+    pub fn seed(&mut self, seed: u64) {
+        self.rng = XorShiftRng::seed_from_u64(node_seed(seed, self.id));
+    }
+
+    pub fn set_tracer(&mut self, tracer: Sender<TraceEvent>) {
+        self.tracer = Some(tracer);
+    }
+
+    pub fn set_algorithm(&mut self, algorithm: Algorithm) {
+        self.algorithm = algorithm;
+    }
+
+    // before restoring a checkpoint, which holds the desire level reached; the
+    // neighbors registered so far start from the same level
+    pub fn set_desire(&mut self, desire: DesireConfig) {
+        self.desire = desire;
+        self.desire_level = desire.initial;
+        for neighbor in self.neighbors.values_mut() {
+            neighbor.desire_level = desire.initial;
+        }
+    }
+
+    pub fn set_weight(&mut self, weight: f32) {
+        self.weight = weight;
+    }
+
+    pub fn set_strategy(&mut self, strategy: Strategy) {
+        self.strategy = Some(strategy);
+    }
+
+    // what carries over to the next round of an mis algorithm, the rest is reset at Start
+    pub fn get_state(&self) -> NodeState {
+        let mut neighbors: Vec<(usize, f32)> = self.neighbors.iter().map(|(id, n)| (*id, n.weight)).collect();
+        neighbors.sort_by_key(|(id, _)| *id);
+        NodeState {
+            id: self.id,
+            desire_level: self.desire_level,
+            neighbors,
+            rng: bincode::serialize(&self.rng).expect("unable to serialize node rng"),
+        }
+    }
+
+    // after registering every neighbor in the graph, the ones gone since are dropped
+    pub fn restore(&mut self, state: &NodeState) {
+        self.neighbors.retain(|id, _| state.neighbors.iter().any(|(n_id, _)| n_id == id));
+        for (id, weight) in state.neighbors.iter() {
+            if let Some(neighbor_context) = self.neighbors.get_mut(id) {
+                neighbor_context.weight = *weight;
+            }
+        }
+        self.desire_level = state.desire_level;
+        self.rng = bincode::deserialize(&state.rng).expect("unable to deserialize node rng");
+    }
+
     pub fn register_neighbor(&mut self, id: usize, sender: Sender<Message>) {
         match self.neighbors.get(&id) {
             Some(_) => (),
             None => {
-                let neighbor_context = NeighborContext::new(sender);
+                let neighbor_context = NeighborContext::new(sender, self.desire.initial);
                 self.neighbors.insert(id, neighbor_context); 
             },
         }
@@ -145,129 +392,460 @@ impl Node {
     }
 
     fn listen(&mut self) {
-        let mut num_neighbor_joined = 0;
-        let mut any_neighbor_joined: bool = false;
         let peer_receiver = self.receiver.take().unwrap();
+        while let Ok(message) = peer_receiver.recv() {
+            if !self.handle_message(message) {
+                break;
+            }
+        }
+        log_event!(Level::Debug, self.round, Some(self.id), None, "Exit", "neighbors={}", self.neighbors.len());
+        drop(peer_receiver);
+    }
+
+    fn record_delivery(&self, message: &Message) {
+        if let Some(tracer) = self.tracer.as_ref() {
+            let from = match message.sender_id() {
+                Some(id) => Endpoint::Node(id),
+                None => Endpoint::Coordinator,
+            };
+            let _ = tracer.send(TraceEvent::Delivery {
+                round: self.round,
+                from,
+                to: Endpoint::Node(self.id),
+                payload: Payload::Node(message.clone()),
+            });
+        }
+    }
+
+    // process one message, returns false once the node has left the network
+    pub fn handle_message(&mut self, message: Message) -> bool {
+        self.record_delivery(&message);
+        log_event!(Level::Trace, self.round, Some(self.id), None, message.kind(), "from={:?} message={:?}", message.sender_id(), message);
+        match message {
+            Message::Request((neighbor_id, round)) => {
+                // may arrive before the start of this round, the answer waits for the mark
+                if self.mark_round == Some(round) {
+                    self.respond(neighbor_id);
+                } else {
+                    self.early_requests.push(neighbor_id);
+                }
+            },
+            Message::Response(data) => {
+                // check if any neighbor get marked
+                self.num_response += 1; 
+                match self.neighbors.get_mut(&data.sender_id) {
+                    Some(neighbor_context) => {
+                        neighbor_context.update(data); 
+                    },
+                    None => unreachable!(),
+                } 
+                if self.num_response == self.neighbors.len() {
+                    self.complete_round1();
+                }
+            },
+            Message::Bit((neighbor_id, _round, bit)) => {
+                match self.neighbors.get_mut(&neighbor_id) {
+                    Some(neighbor_context) => neighbor_context.bits.push_back(bit),
+                    None => unreachable!(),
+                }
+                if self.is_comparing {
+                    self.compare_bits();
+                }
+            },
+            Message::TryColor((neighbor_id, _round, color)) => {
+                // may arrive before the start of this round, so it is only counted here
+                self.num_response += 1;
+                match self.neighbors.get_mut(&neighbor_id) {
+                    Some(neighbor_context) => neighbor_context.color = Some(color),
+                    None => unreachable!(),
+                }
+                if self.is_comparing {
+                    self.check_color();
+                }
+            },
+            Message::Propose((neighbor_id, _round, is_proposing)) => {
+                self.neighbors.get_mut(&neighbor_id).unwrap().proposal = Some(is_proposing);
+                self.advance_matching();
+            },
+            Message::Accept((neighbor_id, _round, is_accepting)) => {
+                self.neighbors.get_mut(&neighbor_id).unwrap().acceptance = Some(is_accepting);
+                self.advance_matching();
+            },
+            Message::Choose((neighbor_id, _round, is_chosen)) => {
+                self.neighbors.get_mut(&neighbor_id).unwrap().choice = Some(is_chosen);
+                self.advance_matching();
+            },
+            Message::StartRound2 if self.algorithm == Algorithm::Matching => {
+                for neighbor_id in self.get_neighbors_id() {
+                    self.send_neighbor(neighbor_id, Message::JoinedMIS((self.partner.is_some(), self.id)));
+                }
+                self.is_deciding = true;
+                if self.num_neighbor_joined == self.neighbors.len() {
+                    self.finish_match_round();
+                }
+            },
+            Message::JoinedMIS((is_neighbor_matched, _neighbor_id)) if self.algorithm == Algorithm::Matching => {
+                self.num_neighbor_joined += 1;
+                if is_neighbor_matched {
+                    self.num_neighbor_matched += 1;
+                }
+                if self.is_deciding && self.num_neighbor_joined == self.neighbors.len() {
+                    self.finish_match_round();
+                }
+            },
+            Message::StartRound2 if self.algorithm == Algorithm::Coloring => {
+                if self.is_get_marked {
+                    log_event!(Level::Info, self.round, Some(self.id), Some(Stage::Round2), "KeepColor", "color={}", self.tentative_color);
+                    self.color = Some(self.tentative_color);
+                }
+                for neighbor_id in self.get_neighbors_id() {
+                    self.send_neighbor(neighbor_id, Message::JoinedMIS((self.is_get_marked, self.id)));
+                }
+                self.is_deciding = true;
+                if self.num_neighbor_joined == self.neighbors.len() {
+                    self.finish_color_round();
+                }
+            },
+            Message::StartRound2 => {
+                // first round exchange phase finishes
+                if !self.is_any_neighbor_marked() && self.is_get_marked {
+                    log_event!(Level::Info, self.round, Some(self.id), Some(Stage::Round2), "JoinMIS", "desire_level={}", self.desire_level);
+                    self.is_in_mis = true;
+                } else if matches!(self.algorithm, Algorithm::Luby | Algorithm::Weighted) {
+                    let effective_degree = self.get_effective_degree();
+                    let old_desire_level = self.desire_level;
+                    self.desire_level = self.desire.update(self.desire_level, effective_degree);
+                    log_event!(Level::Debug, self.round, Some(self.id), Some(Stage::Round2), "DesireUpdate",
+                        "effective_degree={} desire_level={} new_desire_level={}", effective_degree, old_desire_level, self.desire_level);
+                }
+                let is_claiming_mis = self.is_in_mis || self.strategy == Some(Strategy::FakeJoin);
+                for neighbor_id in self.get_neighbors_id() {
+                    self.send_neighbor(neighbor_id, Message::JoinedMIS((is_claiming_mis, self.id)));
+                }
+                // the neighbors may all have answered before this node decided
+                self.is_deciding = true;
+                if self.num_neighbor_joined == self.neighbors.len() {
+                    self.finish_mis_round();
+                }
+            },
+            Message::JoinedMIS((is_neighbor_colored, neighbor_id)) if self.algorithm == Algorithm::Coloring => {
+                self.num_neighbor_joined += 1;
+                if is_neighbor_colored {
+                    if let Some(color) = self.neighbors[&neighbor_id].color {
+                        self.used_colors.insert(color);
+                    }
+                }
+                if self.is_deciding && self.num_neighbor_joined == self.neighbors.len() {
+                    self.finish_color_round();
+                }
+            },
+            Message::JoinedMIS((is_neighbor_joined, _neighbor_id)) => {
+                self.num_neighbor_joined += 1;
+                self.any_neighbor_joined |= is_neighbor_joined;
+                if self.is_deciding && self.num_neighbor_joined == self.neighbors.len() {
+                    self.finish_mis_round();
+                }
+            },
+            Message::RemoveNeighbors(neighbors_id) => {
+                for neighbor_id in neighbors_id.iter() {
+                    if *neighbor_id == self.id {
+                        self.clean_neighbors();
+                        return false; 
+                    }
+                    self.neighbors.remove(neighbor_id);
+                }
+                let mut reconfig_result = ReconfigResult {
+                    id: self.id,
+                    is_leaving: false,
+                };
+
+                // an isolated node still has to pick its color
+                if self.neighbors.is_empty() && !matches!(self.algorithm, Algorithm::Coloring | Algorithm::Matching) {
+                    log_event!(Level::Info, self.round, Some(self.id), Some(Stage::Reconfigure), "JoinMIS", "isolated");
+                    reconfig_result.is_leaving = true;
+                    self.central_sender.send(CentralMessage::ReconfigComplete(reconfig_result)).expect("central send fail");
+                    return false;
+                } 
+                
+                self.central_sender.send(CentralMessage::ReconfigComplete(reconfig_result)).expect("unable to send reconfigcomplete");
+            },
+            Message::Start(round) => {
+                self.round = round;
+                match self.algorithm {
+                    Algorithm::Luby | Algorithm::Weighted => {
+                        self.num_response = 0;
+                        self.is_get_marked = self.decide_if_get_mark();
+                        self.mark_round = Some(round);
+                        log_event!(Level::Debug, self.round, Some(self.id), Some(Stage::Start), "Mark",
+                            "is_get_marked={} desire_level={} neighbors={}", self.is_get_marked, self.desire_level, self.neighbors.len());
+                        self.request_all_neighbors();
+                        for neighbor_id in std::mem::take(&mut self.early_requests) {
+                            self.respond(neighbor_id);
+                        }
+                        // isolated from the start, no response will come and nothing
+                        // keeps the node out of the mis
+                        if self.neighbors.is_empty() {
+                            self.is_get_marked = true;
+                            self.complete_round1();
+                        }
+                    },
+                    Algorithm::Metivier => {
+                        // marked means not beaten by any neighbor yet
+                        self.is_get_marked = true;
+                        self.is_comparing = true;
+                        self.num_bit = 0;
+                        self.tied = self.get_neighbors_id().into_iter().collect();
+                        self.send_bit();
+                        self.compare_bits();
+                    },
+                    Algorithm::Coloring => self.try_color(),
+                    Algorithm::Matching => self.propose(),
+                }
+            },
+            Message::Checkpoint => {
+                self.central_sender.send(CentralMessage::State(self.get_state())).expect("unable to send State");
+            },
+        } 
+        true
+    }
+
+    fn complete_round1(&mut self) {
+        log_event!(Level::Debug, self.round, Some(self.id), Some(Stage::Round1), "Round1Complete",
+            "responses={} marked_neighbors={:?}", self.num_response, self.marked_neighbors());
+        self.central_sender.send(CentralMessage::Round1Complete((self.id, self.is_get_marked, 0))).expect("unable to send Round1Complete");
+    }
+
+    // every neighbor told whether it joined, or there is none
+    fn finish_mis_round(&mut self) {
+        if self.any_neighbor_joined | self.is_in_mis {
+            let result = NodeResult {
+                id: self.id,
+                is_in_mis: self.is_in_mis,
+                nodes_to_remove: vec![self.id],
+                color: None,
+                partner: None,
+            };
+            self.central_sender.send(CentralMessage::Finish(result)).expect("unable to send to central");
+            log_event!(Level::Info, self.round, Some(self.id), Some(Stage::Round2), "Finish", "is_in_mis={}", self.is_in_mis);
+        } else {
+            log_event!(Level::Debug, self.round, Some(self.id), Some(Stage::Round2), "Step", "stay, marked_neighbors={:?}", self.marked_neighbors());
+            self.central_sender.send(CentralMessage::Step(self.id)).expect("central send fail");
+        }
+        self.num_neighbor_joined = 0;
+        self.is_deciding = false;
+    }
+
+    fn send_bit(&mut self) {
+        self.bit = self.rng.gen();
+        let mut tied: Vec<usize> = self.tied.iter().cloned().collect();
+        tied.sort();
+        for neighbor_id in tied {
+            self.num_bit += 1;
+            self.send_neighbor(neighbor_id, Message::Bit((self.id, self.round, self.bit)));
+        }
+        let neighbors = &self.neighbors;
+        self.tied.retain(|n_id| neighbors.contains_key(n_id));
+    }
+
+    // once a bit has arrived from every tied neighbor, a node with 0 against 1 is beaten
+    // and both stop comparing; when no tie is left the node reports whether it beat all
+    // its neighbors, counting the JoinedMIS bit it will send to each of them
+    fn compare_bits(&mut self) {
+        loop {
+            if self.tied.iter().any(|n_id| self.neighbors[n_id].bits.is_empty()) {
+                return;
+            }
+            let tied: Vec<usize> = self.tied.iter().cloned().collect();
+            for n_id in tied {
+                let bit = self.neighbors.get_mut(&n_id).unwrap().bits.pop_front().unwrap();
+                if bit != self.bit {
+                    self.tied.remove(&n_id);
+                    if bit {
+                        self.is_get_marked = false;
+                    }
+                }
+            }
+            if self.tied.is_empty() {
+                self.is_comparing = false;
+                let num_bit = self.num_bit + self.neighbors.len();
+                log_event!(Level::Debug, self.round, Some(self.id), Some(Stage::Round1), "Round1Complete",
+                    "is_get_marked={} bits={}", self.is_get_marked, num_bit);
+                self.central_sender.send(CentralMessage::Round1Complete((self.id, self.is_get_marked, num_bit))).expect("unable to send Round1Complete");
+                return;
+            }
+            self.send_bit();
+        }
+    }
+
+    // the palette is fixed by the degree in the first round, so a free color always remains
+    fn try_color(&mut self) {
+        if self.round == 0 {
+            self.palette = self.neighbors.len() + 1;
+        }
+        let free: Vec<usize> = (0..self.palette).filter(|c| !self.used_colors.contains(c)).collect();
+        self.tentative_color = free[self.rng.gen_range(0, free.len())];
+        log_event!(Level::Debug, self.round, Some(self.id), Some(Stage::Start), "TryColor",
+            "color={} free={}", self.tentative_color, free.len());
+        for neighbor_id in self.get_neighbors_id() {
+            self.send_neighbor(neighbor_id, Message::TryColor((self.id, self.round, self.tentative_color)));
+        }
+        self.is_comparing = true;
+        self.check_color();
+    }
+
+    // the color is kept when no neighbor tries the same one
+    fn check_color(&mut self) {
+        if self.num_response < self.neighbors.len() {
+            return;
+        }
+        let tentative_color = self.tentative_color;
+        self.is_get_marked = self.neighbors.values().all(|n| n.color != Some(tentative_color));
+        self.is_comparing = false;
+        self.num_response = 0;
+        self.central_sender.send(CentralMessage::Round1Complete((self.id, self.is_get_marked, 0))).expect("unable to send Round1Complete");
+    }
+
+    fn finish_color_round(&mut self) {
+        self.num_neighbor_joined = 0;
+        self.is_deciding = false;
+        if self.color.is_some() {
+            let result = NodeResult {
+                id: self.id,
+                is_in_mis: false,
+                nodes_to_remove: vec![self.id],
+                color: self.color,
+                partner: None,
+            };
+            log_event!(Level::Info, self.round, Some(self.id), Some(Stage::Round2), "Finish", "color={:?}", self.color);
+            self.central_sender.send(CentralMessage::Finish(result)).expect("unable to send to central");
+        } else {
+            self.central_sender.send(CentralMessage::Step(self.id)).expect("central send fail");
+        }
+    }
+
+    fn sorted_neighbors_id(&self) -> Vec<usize> {
+        let mut ids = self.get_neighbors_id();
+        ids.sort();
+        ids
+    }
+
+    // Israeli-Itai: every node proposes to a random neighbor and accepts one random
+    // proposal, which leaves paths and cycles; each node then chooses one of its
+    // proposal edges and an edge chosen at both ends is matched
+    fn propose(&mut self) {
+        let ids = self.sorted_neighbors_id();
+        self.proposee = if ids.is_empty() { None } else { Some(ids[self.rng.gen_range(0, ids.len())]) };
+        for neighbor_id in ids {
+            let is_proposing = self.proposee == Some(neighbor_id);
+            self.send_neighbor(neighbor_id, Message::Propose((self.id, self.round, is_proposing)));
+        }
+        self.match_step = MatchStep::Propose;
+        self.advance_matching();
+    }
+
+    // every step waits for one message from each neighbor it concerns, messages of the
+    // next steps can arrive earlier and are kept in the neighbor context
+    fn advance_matching(&mut self) {
         loop {
-            
-
-            match peer_receiver.recv() {
-                Ok(message) => {
-                    match message {
-                        Message::Request((neighbor_id, round)) => {
-                            //println!("{}.{} Message::Request {} -> {}. request round {}", self.round, self.id, neighbor_id, self.id, round);
-                            let data = Data {
-                                round: self.round,
-                                sender_id: self.id,
-                                desire_level: self.desire_level,
-                                is_get_marked: self.is_get_marked,
-                            };
-                            self.send_neighbor(neighbor_id, Message::Response(data));
-                        },
-                        Message::Response(data) => {
-                            //println!("{}.{} Message::Response", self.round, self.id);
-                            // check if any neighbor get marked
-                            self.num_response += 1; 
-                            match self.neighbors.get_mut(&data.sender_id) {
-                                Some(neighbor_context) => {
-                                    neighbor_context.update(data); 
-                                },
-                                None => unreachable!(),
-                            } 
-                            if self.num_response == self.neighbors.len() {
-                                //println!("{}.{} Message::Response {} {}", self.round, self.id, self.num_response, self.neighbors.len());
-                                self.central_sender.send(CentralMessage::Round1Complete).expect("unable to send Round1Complete"); 
-                            }
-                        },
-                        Message::StartRound2 => {
-                            //println!("{}.{} Message::StartRound2", self.round, self.id);
-                            // first round exchange phase finishes
-                            if !self.is_any_neighbor_marked() && self.is_get_marked {
-                                //println!("{} join mis", self.id);
-                                self.is_in_mis = true;
-                            } else {
-                                let effective_degree = self.get_effective_degree();
-                                if effective_degree >= 2.0 {
-                                    self.desire_level =  self.desire_level / 2.0;
-                                } else {
-                                    self.desire_level = (2.0*self.desire_level).min(0.5);
-                                }
-                            }
-                            let neighbors: Vec<usize> = self.neighbors.keys().map(|&x| x).collect();
-                            for neighbor_id in neighbors {
-                                self.send_neighbor(neighbor_id, Message::JoinedMIS((self.is_in_mis, self.id)));
-                            }
-                        },
-                        Message::JoinedMIS((is_neighbor_joined, neighbor_id)) => {
-                            //println!("{}.{} Message::JoinedMIS {} {}", self.round, self.id, is_neighbor_joined, neighbor_id);
-                            num_neighbor_joined += 1;
-                            any_neighbor_joined = any_neighbor_joined | is_neighbor_joined;
-                            if num_neighbor_joined == self.neighbors.len() {
-                                if any_neighbor_joined | self.is_in_mis {
-                                    let result = NodeResult {
-                                        id: self.id,
-                                        is_in_mis: self.is_in_mis,
-                                        nodes_to_remove: vec![self.id],
-                                    };
-                                    self.central_sender.send(CentralMessage::Finish(result)).expect("unable to send to central");  
-                                    //println!("        {} leave network", self.id);
-                                } else {
-                                    //println!("        {} stay network {:?} ", self.id, self.debug_mark());
-                                    self.central_sender.send(CentralMessage::Step(self.id)).expect("central send fail");  
-                                }
-                                num_neighbor_joined = 0;
-                            }
-                        },
-                        Message::RemoveNeighbors(neighbors_id) => {
-                            //println!("{}.{} Message RemoveNeighbors {:?}", self.round,self.id, neighbors_id);
-                            for neighbor_id in neighbors_id.iter() {
-                                if *neighbor_id == self.id {
-                                    self.clean_neighbors();
-                                    drop(peer_receiver);
-                                    return; 
-                                }
-                                self.neighbors.remove(neighbor_id);
-                            }
-                            let mut reconfig_result = ReconfigResult {
-                                id: self.id,
-                                is_leaving: false,
-                            };
-
-                            if self.neighbors.len() == 0{
-                                let result = NodeResult {
-                                    id: self.id,
-                                    is_in_mis: true,
-                                    nodes_to_remove: vec![self.id],
-                                };
-                                //println!("{}. id {} Join MIS", self.round, self.id);
-                                reconfig_result.is_leaving = true;
-                                self.central_sender.send(CentralMessage::ReconfigComplete(reconfig_result)).expect("central send fail");
-                                //println!("{} thread exit", self.id);
-                                break;
-                            } 
-                            
-                            self.central_sender.send(CentralMessage::ReconfigComplete(reconfig_result)).expect("unable to send reconfigcomplete");
-                        },
-                        Message::Start(round) => {
-                            //println!("{}.{} Message::Start", round, self.id);
-                            // join MIS if there is no neighbors
-                            
-
-                            self.num_response = 0;
-                            self.round = round;
-                            self.is_get_marked = self.decide_if_get_mark();
-                            self.request_all_neighbors();
-
-                        },
-                    } 
+            match self.match_step {
+                MatchStep::Idle => return,
+                MatchStep::Propose => {
+                    if self.neighbors.values().any(|n| n.proposal.is_none()) {
+                        return;
+                    }
+                    let proposers: Vec<usize> = self.sorted_neighbors_id().into_iter()
+                        .filter(|n_id| self.neighbors[n_id].proposal == Some(true))
+                        .collect();
+                    let accepted = if proposers.is_empty() { None } else { Some(proposers[self.rng.gen_range(0, proposers.len())]) };
+                    for neighbor_id in self.sorted_neighbors_id() {
+                        self.send_neighbor(neighbor_id, Message::Accept((self.id, self.round, accepted == Some(neighbor_id))));
+                    }
+                    self.match_edges = accepted.into_iter().collect();
+                    self.match_step = MatchStep::Accept;
+                },
+                MatchStep::Accept => {
+                    if self.neighbors.values().any(|n| n.acceptance.is_none()) {
+                        return;
+                    }
+                    if let Some(proposee) = self.proposee {
+                        if self.neighbors[&proposee].acceptance == Some(true) && !self.match_edges.contains(&proposee) {
+                            self.match_edges.push(proposee);
+                        }
+                    }
+                    self.match_edges.sort();
+                    let choice = if self.match_edges.is_empty() {
+                        None
+                    } else {
+                        Some(self.match_edges[self.rng.gen_range(0, self.match_edges.len())])
+                    };
+                    for neighbor_id in self.match_edges.clone() {
+                        self.send_neighbor(neighbor_id, Message::Choose((self.id, self.round, choice == Some(neighbor_id))));
+                    }
+                    self.match_choice = choice;
+                    self.match_step = MatchStep::Choose;
                 },
-                Err(e) => {
-                    //println!("{} thread exit", self.id);
-                    break; //println!("node {} try recv error {:?}", self.id, e),
+                MatchStep::Choose => {
+                    if self.match_edges.iter().any(|n_id| self.neighbors[n_id].choice.is_none()) {
+                        return;
+                    }
+                    if let Some(choice) = self.match_choice {
+                        if self.neighbors[&choice].choice == Some(true) {
+                            self.partner = Some(choice);
+                        }
+                    }
+                    for neighbor_context in self.neighbors.values_mut() {
+                        neighbor_context.proposal = None;
+                        neighbor_context.acceptance = None;
+                        neighbor_context.choice = None;
+                    }
+                    self.match_step = MatchStep::Idle;
+                    log_event!(Level::Debug, self.round, Some(self.id), Some(Stage::Round1), "Round1Complete",
+                        "match_edges={:?} partner={:?}", self.match_edges, self.partner);
+                    self.central_sender.send(CentralMessage::Round1Complete((self.id, self.partner.is_some(), 0))).expect("unable to send Round1Complete");
+                    return;
                 },
-            } 
+            }
         }
-        drop(peer_receiver);
+    }
+
+    // a matched node leaves with its partner, an unmatched one leaves once all its neighbors are matched
+    fn finish_match_round(&mut self) {
+        let is_done = self.partner.is_some() || self.num_neighbor_matched == self.neighbors.len();
+        self.num_neighbor_joined = 0;
+        self.is_deciding = false;
+        self.num_neighbor_matched = 0;
+        if is_done {
+            let result = NodeResult {
+                id: self.id,
+                is_in_mis: false,
+                nodes_to_remove: vec![self.id],
+                color: None,
+                partner: self.partner,
+            };
+            log_event!(Level::Info, self.round, Some(self.id), Some(Stage::Round2), "Finish", "partner={:?}", self.partner);
+            self.central_sender.send(CentralMessage::Finish(result)).expect("unable to send to central");
+        } else {
+            self.central_sender.send(CentralMessage::Step(self.id)).expect("central send fail");
+        }
+    }
+
+    // everything that decides what the node does next, the rng by its next draw, so that
+    // states reached by different interleavings can be compared
+    pub fn fingerprint(&self) -> String {
+        let mut neighbors: Vec<String> = self.neighbors.iter().map(|(id, n)| {
+            format!("{}:{:?}", id, (n.round, n.is_marked, n.desire_level, n.weight, &n.bits, n.color, n.proposal, n.acceptance, n.choice))
+        }).collect();
+        neighbors.sort();
+        let mut tied: Vec<&usize> = self.tied.iter().collect();
+        tied.sort();
+        let mut used_colors: Vec<&usize> = self.used_colors.iter().collect();
+        used_colors.sort();
+        format!("{:?} {:?} {:?} {}",
+            (self.id, self.round, self.desire_level, self.weight, self.is_get_marked, self.mark_round, &self.early_requests, self.num_response, self.is_in_mis),
+            (self.num_neighbor_joined, self.is_deciding, self.any_neighbor_joined, self.algorithm, self.strategy, tied, self.bit, self.is_comparing, self.num_bit),
+            (self.palette, self.color, self.tentative_color, used_colors, self.match_step, self.proposee, &self.match_edges, self.match_choice, self.partner, self.num_neighbor_matched),
+            self.rng.clone().gen::<u64>(),
+        ) + &neighbors.join(",")
     }
 
     fn get_neighbors_id(&self) -> Vec<usize> {
@@ -278,42 +856,77 @@ impl Node {
         ids
     }
 
-    fn decide_if_get_mark(&self) -> bool {
-        let rand_float = thread_rng().gen_range(0.0, 1.0);
-        //println!("float {}", rand_float);
-        return rand_float < self.desire_level;
+    fn decide_if_get_mark(&mut self) -> bool {
+        let rand_float = self.rng.gen_range(0.0, 1.0);
+        if self.algorithm == Algorithm::Weighted {
+            return rand_float < self.desire_level * self.weight_share();
+        }
+        rand_float < self.desire_level
+    }
+
+    // own weight over the heaviest one around, weights of neighbors are learned from
+    // their responses so every node marks at its desire level in the first round
+    fn weight_share(&self) -> f32 {
+        let heaviest = self.neighbors.values().map(|n| n.weight).fold(self.weight, f32::max);
+        if heaviest > 0.0 { self.weight / heaviest } else { 1.0 }
+    }
+
+    fn respond(&mut self, neighbor_id: usize) {
+        let mut data = Data {
+            round: self.round,
+            sender_id: self.id,
+            desire_level: self.desire_level,
+            weight: self.weight,
+            is_get_marked: self.is_get_marked,
+        };
+        match self.strategy {
+            Some(Strategy::AlwaysMarked) => data.is_get_marked = true,
+            Some(Strategy::FalseDesire) => data.desire_level = FALSE_DESIRE_LEVEL,
+            _ => (),
+        }
+        self.send_neighbor(neighbor_id, Message::Response(data));
     }
 
     fn request_all_neighbors(&mut self) {
-        let neighbors: Vec<usize> = self.neighbors.keys().map(|&x| x).collect();
-        for neighbor_id in neighbors {
+        for neighbor_id in self.get_neighbors_id() {
             self.send_neighbor(neighbor_id, Message::Request((self.id, self.round)));
         }
     } 
 
+    // summed by neighbor id, float rounding depends on the order once desire levels are
+    // not powers of 2, and hash map order changes from one run to the next
     fn get_effective_degree(&self) -> f32 {
+        let mut ids = self.get_neighbors_id();
+        ids.sort();
         let mut effective_degree: f32 = 0.0;
-        for (_, neighbor_context) in self.neighbors.iter() {
-            effective_degree += neighbor_context.desire_level; 
-        }    
+        for id in ids.iter() {
+            effective_degree += self.neighbors[id].desire_level;
+        }
         effective_degree
     }
 
-    fn debug_mark(&self) -> Vec<(usize, bool)> {
-        let mut marked_vec: Vec<(usize, bool)> = vec![];
+    fn marked_neighbors(&self) -> Vec<usize> {
+        let mut marked: Vec<usize> = vec![];
         for (id, neighbor_context) in self.neighbors.iter() {
-            marked_vec.push((*id, neighbor_context.is_marked));
+            if neighbor_context.is_marked {
+                marked.push(*id);
+            }
         }
-        marked_vec
+        marked
     }
 
+    // with weights only a heavier marked neighbor counts, ties go to the larger id
     fn is_any_neighbor_marked(&self) -> bool {
-        for (_, neighbor_context) in self.neighbors.iter() {
+        for (id, neighbor_context) in self.neighbors.iter() {
+            if self.algorithm == Algorithm::Weighted
+                && (neighbor_context.weight, *id) < (self.weight, self.id) {
+                continue;
+            }
             if  neighbor_context.is_marked {
                 return true; 
             }
         }
-        return false;
+        false
     }
 
 
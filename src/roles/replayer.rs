@@ -0,0 +1,154 @@
+use super::node::{Node, Message, CentralMessage};
+use super::coordinator::{Coordinator};
+use super::graph::{undirected_neighbors};
+use super::tracer::{TraceEvent, TraceHeader, Endpoint, Payload};
+
+use std::sync::mpsc::{Sender, Receiver, channel};
+use std::collections::{VecDeque};
+
+#[derive(Debug)]
+pub struct Divergence {
+    pub step: usize,
+    pub expected: TraceEvent,
+    pub found: String,
+}
+
+// single threaded re-execution of a recorded run: messages are delivered in the
+// recorded order and every event of the fresh run is compared with the trace
+pub struct Replayer {
+    pub nodes: Vec<Option<Node>>,
+    pub receivers: Vec<Option<Receiver<Message>>>,
+    pub pending: Vec<VecDeque<Message>>,
+    pub central_pending: VecDeque<CentralMessage>,
+    pub coordinator: Coordinator,
+    pub fresh_events: Receiver<TraceEvent>,
+}
+
+impl Replayer {
+    pub fn new(header: &TraceHeader) -> Replayer {
+        let graph = &header.graph;
+        let num_node = graph.len();
+        let (central_sender, central_receiver) = channel();
+        let (event_sender, event_receiver) = channel();
+        let mut node_list: Vec<Node> = vec![];
+        let mut sender_list: Vec<Sender<Message>> = vec![];
+        let mut receivers: Vec<Option<Receiver<Message>>> = vec![];
+
+        for i in 0..num_node {
+            let (mut node, sender_to_node) = Node::new(i, central_sender.clone());
+            node.seed(header.seed);
+            node.set_algorithm(header.algorithm);
+            node.set_desire(header.desire);
+            node.set_weight(header.weights[i]);
+            if let Some(strategy) = header.strategies[i] {
+                node.set_strategy(strategy);
+            }
+            node.set_tracer(event_sender.clone());
+            receivers.push(node.receiver.take());
+            node_list.push(node);
+            sender_list.push(sender_to_node);
+        }
+        for (i, neighbors) in undirected_neighbors(graph).iter().enumerate() {
+            for n_id in neighbors.iter() {
+                node_list[i].register_neighbor(*n_id, sender_list[*n_id].clone());
+            }
+        }
+        let mut coordinator = Coordinator::new(sender_list, central_receiver);
+        coordinator.set_tracer(event_sender);
+        if let Some(round) = header.stop_round {
+            coordinator.set_snapshot(round, true);
+        }
+
+        Replayer {
+            nodes: node_list.into_iter().map(Some).collect(),
+            receivers,
+            pending: vec![VecDeque::new(); num_node],
+            central_pending: VecDeque::new(),
+            coordinator,
+            fresh_events: event_receiver,
+        }
+    }
+
+    fn collect_pending(&mut self) {
+        for (i, receiver) in self.receivers.iter().enumerate() {
+            if let Some(receiver) = receiver {
+                while let Ok(message) = receiver.try_recv() {
+                    self.pending[i].push_back(message);
+                }
+            }
+        }
+        while let Ok(central_message) = self.coordinator.central_receiver.try_recv() {
+            self.central_pending.push_back(central_message);
+        }
+    }
+
+    // messages from different senders may be enqueued in another order than in the
+    // recorded run, so any pending message with the same content is accepted
+    fn take_pending<T: PartialEq>(queue: &mut VecDeque<T>, expected: &T) -> Option<T> {
+        let index = queue.iter().position(|m| m == expected)?;
+        queue.remove(index)
+    }
+
+    // perform the action described by a recorded event, the fresh run records its own event
+    fn apply(&mut self, event: &TraceEvent) -> std::result::Result<(), String> {
+        self.collect_pending();
+        match event {
+            TraceEvent::Delivery { to: Endpoint::Node(id), payload: Payload::Node(message), .. } => {
+                if self.nodes[*id].is_none() {
+                    return Err(format!("node {} has already left the network", id));
+                }
+                let message = match Replayer::take_pending(&mut self.pending[*id], message) {
+                    Some(message) => message,
+                    None => return Err(format!("message is not pending, node {} has {:?}", id, self.pending[*id])),
+                };
+                let is_staying = self.nodes[*id].as_mut().unwrap().handle_message(message);
+                if !is_staying {
+                    self.nodes[*id] = None;
+                    self.receivers[*id] = None;
+                }
+            },
+            TraceEvent::Delivery { to: Endpoint::Coordinator, payload: Payload::Central(central_message), .. } => {
+                let central_message = match Replayer::take_pending(&mut self.central_pending, central_message) {
+                    Some(central_message) => central_message,
+                    None => return Err(format!("message is not pending, coordinator has {:?}", self.central_pending)),
+                };
+                self.coordinator.handle_central_message(central_message);
+            },
+            TraceEvent::Delivery { .. } => return Err("malformed delivery".to_string()),
+            TraceEvent::Stage { .. } => {
+                if !self.coordinator.advance() {
+                    return Err(format!("coordinator stays in {:?}", self.coordinator.stage));
+                }
+            },
+            TraceEvent::End(_) => {
+                if !self.coordinator.is_finished() {
+                    return Err(format!("coordinator has {} results out of {}", self.coordinator.result_list.len(), self.coordinator.num_node));
+                }
+                self.coordinator.finish();
+            },
+        }
+        let fresh: Vec<TraceEvent> = self.fresh_events.try_iter().collect();
+        if fresh.len() != 1 || fresh[0] != *event {
+            let found: Vec<String> = fresh.iter().map(|e| e.describe()).collect();
+            return Err(found.join("; "));
+        }
+        Ok(())
+    }
+
+    // returns the number of replayed events, or the first divergence
+    pub fn run(&mut self, events: &[TraceEvent], verbose: bool) -> std::result::Result<usize, Box<Divergence>> {
+        for (step, event) in events.iter().enumerate() {
+            if verbose {
+                println!("{} {}", step, event.describe());
+            }
+            if let Err(found) = self.apply(event) {
+                return Err(Box::new(Divergence {
+                    step,
+                    expected: event.clone(),
+                    found,
+                }));
+            }
+        }
+        Ok(events.len())
+    }
+}
@@ -0,0 +1,220 @@
+use super::coordinator::{RoundState};
+use super::graph::{undirected_neighbors};
+use super::node::{node_seed};
+use rand::{Rng, SeedableRng};
+use rand::prng::XorShiftRng;
+use std::collections::{HashMap, HashSet};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum RulingState {
+    Undecided,
+    Ruler, //in the set
+    Ruled, //a ruler is close enough
+}
+
+// a node only talks to its neighbors, what it learns from farther away is relayed
+// hop by hop, and it keeps relaying after it decided since distances are measured
+// in the whole graph
+pub struct RulingNode {
+    pub id: usize,
+    pub state: RulingState,
+    pub known: HashMap<usize, u64>, //origin, value heard within the current flood
+    pub fresh: Vec<(usize, u64)>, //learned in the last hop, relayed in the next one
+    pub rng: XorShiftRng,
+}
+
+impl RulingNode {
+    pub fn new(id: usize, seed: u64) -> RulingNode {
+        RulingNode {
+            id,
+            state: RulingState::Undecided,
+            known: HashMap::new(),
+            fresh: vec![],
+            rng: XorShiftRng::seed_from_u64(node_seed(seed, id)),
+        }
+    }
+
+    fn start_flood(&mut self, value: Option<u64>) {
+        self.known.clear();
+        self.fresh.clear();
+        if let Some(value) = value {
+            self.known.insert(self.id, value);
+            self.fresh.push((self.id, value));
+        }
+    }
+
+    fn receive(&mut self, entries: &[(usize, u64)], next: &mut Vec<(usize, u64)>) {
+        for (origin, value) in entries.iter() {
+            if !self.known.contains_key(origin) {
+                self.known.insert(*origin, *value);
+                next.push((*origin, *value));
+            }
+        }
+    }
+}
+
+// (alpha, beta)-ruling sets in synchronous rounds: rulers are at distance alpha or
+// more from each other and every node is within distance beta of a ruler, an mis of
+// the graph power G^k is a (k + 1, k)-ruling set
+pub struct RulingSimulator {
+    pub neighbors: Vec<Vec<usize>>,
+    pub nodes: Vec<RulingNode>,
+    pub num_round: usize, //communication rounds
+    pub num_message: usize,
+    pub iteration: usize,
+    pub decide_round: Vec<Option<usize>>,
+    pub history: Vec<RoundState>,
+}
+
+impl RulingSimulator {
+    pub fn new(graph: &[Vec<usize>], seed: u64) -> RulingSimulator {
+        let neighbors = undirected_neighbors(graph);
+        RulingSimulator {
+            neighbors,
+            nodes: (0..graph.len()).map(|id| RulingNode::new(id, seed)).collect(),
+            num_round: 0,
+            num_message: 0,
+            iteration: 0,
+            decide_round: vec![None; graph.len()],
+            history: vec![],
+        }
+    }
+
+    // every node with a value floods it for the given number of hops, afterwards each
+    // node knows the values of the nodes within that distance
+    fn flood(&mut self, values: &[Option<u64>], hops: usize) {
+        for (node, value) in self.nodes.iter_mut().zip(values.iter()) {
+            node.start_flood(*value);
+        }
+        for _ in 0..hops {
+            self.num_round += 1;
+            let outgoing: Vec<Vec<(usize, u64)>> = self.nodes.iter_mut().map(|n| std::mem::take(&mut n.fresh)).collect();
+            let mut incoming: Vec<Vec<(usize, u64)>> = vec![vec![]; self.nodes.len()];
+            for (id, entries) in outgoing.iter().enumerate() {
+                if entries.is_empty() {
+                    continue;
+                }
+                for n_id in self.neighbors[id].iter() {
+                    self.num_message += 1;
+                    incoming[*n_id].extend(entries.iter().cloned());
+                }
+            }
+            for (node, entries) in self.nodes.iter_mut().zip(incoming.iter()) {
+                let mut next: Vec<(usize, u64)> = vec![];
+                node.receive(entries, &mut next);
+                node.fresh = next;
+            }
+        }
+    }
+
+    fn decide(&mut self, id: usize, state: RulingState, round_state: &mut RoundState) {
+        self.nodes[id].state = state;
+        self.decide_round[id] = Some(self.iteration);
+        match state {
+            RulingState::Ruler => round_state.joined.push(id),
+            _ => round_state.removed.push(id),
+        }
+    }
+
+    // Luby on G^k: undecided nodes flood random priorities k hops, a node with the
+    // highest priority within distance k joins, and joins are flooded k hops
+    pub fn distance_mis(&mut self, k: usize) {
+        let num_node = self.nodes.len();
+        while self.nodes.iter().any(|n| n.state == RulingState::Undecided) {
+            let mut round_state = RoundState::new(self.iteration);
+            let priorities: Vec<Option<u64>> = self.nodes.iter_mut().map(|n| {
+                if n.state == RulingState::Undecided { Some(n.rng.gen()) } else { None }
+            }).collect();
+            self.flood(&priorities, k);
+            let winners: Vec<usize> = (0..num_node).filter(|id| {
+                let own = match priorities[*id] {
+                    Some(priority) => (priority, *id),
+                    None => return false,
+                };
+                self.nodes[*id].known.iter().all(|(origin, priority)| (*priority, *origin) <= own)
+            }).collect();
+            round_state.marked = winners.clone();
+            for id in winners.iter() {
+                self.decide(*id, RulingState::Ruler, &mut round_state);
+            }
+
+            let joins: Vec<Option<u64>> = (0..num_node).map(|id| {
+                if round_state.joined.contains(&id) { Some(1) } else { None }
+            }).collect();
+            self.flood(&joins, k);
+            for id in 0..num_node {
+                if self.nodes[id].state == RulingState::Undecided && !self.nodes[id].known.is_empty() {
+                    self.decide(id, RulingState::Ruled, &mut round_state);
+                }
+            }
+            self.history.push(round_state);
+            self.iteration += 1;
+        }
+    }
+
+    // Awerbuch-Goldberg-Luby-Plotkin: going up the bits of the ids, candidates whose
+    // bit is 1 drop out when a candidate of the same id prefix with bit 0 is within
+    // alpha - 1 hops, which gives an (alpha, (alpha - 1) * bits)-ruling set without randomness
+    pub fn bit_ruling_set(&mut self, alpha: usize) {
+        let num_node = self.nodes.len();
+        let num_bit = id_bits(num_node);
+        let mut is_candidate = vec![true; num_node];
+        for bit in 0..num_bit {
+            let mut round_state = RoundState::new(self.iteration);
+            let values: Vec<Option<u64>> = (0..num_node).map(|id| {
+                if is_candidate[id] && (id >> bit) & 1 == 0 { Some(0) } else { None }
+            }).collect();
+            self.flood(&values, alpha - 1);
+            for (id, is_node_candidate) in is_candidate.iter_mut().enumerate() {
+                if !*is_node_candidate || (id >> bit) & 1 == 0 {
+                    continue;
+                }
+                let group = id >> (bit + 1);
+                if self.nodes[id].known.keys().any(|origin| origin >> (bit + 1) == group) {
+                    *is_node_candidate = false;
+                    self.decide(id, RulingState::Ruled, &mut round_state);
+                }
+            }
+            self.history.push(round_state);
+            self.iteration += 1;
+        }
+        let mut round_state = RoundState::new(self.iteration.saturating_sub(1));
+        for (id, is_node_candidate) in is_candidate.iter().enumerate() {
+            if *is_node_candidate {
+                self.decide(id, RulingState::Ruler, &mut round_state);
+            }
+        }
+        match self.history.last_mut() {
+            Some(last) => last.joined = round_state.joined,
+            None => self.history.push(round_state),
+        }
+    }
+
+    // the deterministic bit splitting is used when beta allows it, an mis of G^(alpha - 1)
+    // otherwise, returns the beta that is guaranteed
+    pub fn ruling_set(&mut self, alpha: usize, beta: usize) -> Result<usize, String> {
+        if alpha < 2 {
+            return Err(format!("alpha must be at least 2, get {}", alpha));
+        }
+        if beta + 1 < alpha {
+            return Err(format!("an ({}, {})-ruling set may not exist, beta must be at least alpha - 1", alpha, beta));
+        }
+        let bit_beta = (alpha - 1) * id_bits(self.nodes.len());
+        if beta >= bit_beta {
+            self.bit_ruling_set(alpha);
+            Ok(bit_beta)
+        } else {
+            self.distance_mis(alpha - 1);
+            Ok(alpha - 1)
+        }
+    }
+
+    pub fn get_rulers(&self) -> HashSet<usize> {
+        self.nodes.iter().filter(|n| n.state == RulingState::Ruler).map(|n| n.id).collect()
+    }
+}
+
+// number of bits of the largest node id
+fn id_bits(num_node: usize) -> usize {
+    (usize::BITS - num_node.saturating_sub(1).leading_zeros()) as usize
+}
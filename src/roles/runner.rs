@@ -0,0 +1,132 @@
+use super::node::{Node, Message, Algorithm};
+use super::coordinator::{Coordinator};
+use super::byzantine::{Strategy};
+use super::checkpoint::{Checkpoint};
+use super::desire::{DesireConfig};
+use super::graph::{undirected_neighbors};
+use super::tracer::{TraceEvent};
+
+use std::collections::{HashSet};
+use std::sync::mpsc::{Sender, channel};
+use std::time::{Duration, Instant};
+
+// settings of one run of the nodes in their own threads, every run of the message
+// passing algorithms in threads goes through run_threaded
+#[derive(Clone)]
+pub struct RunConfig {
+    pub algorithm: Algorithm,
+    pub seed: u64,
+    pub desire: DesireConfig,
+    pub strategies: Vec<Option<Strategy>>, //by node id, all honest when empty
+    pub snapshot: Option<(usize, bool)>, //round at which the undecided nodes are recorded, the run stops there
+    pub time_limit: Option<Duration>,
+    pub tracer: Option<Sender<TraceEvent>>,
+    pub checkpoint: Option<(String, usize)>, //path, rounds between two checkpoints
+    pub resume: Option<Checkpoint>,
+}
+
+impl RunConfig {
+    pub fn new(algorithm: Algorithm, seed: u64) -> RunConfig {
+        RunConfig {
+            algorithm,
+            seed,
+            desire: DesireConfig::default(),
+            strategies: vec![],
+            snapshot: None,
+            time_limit: None,
+            tracer: None,
+            checkpoint: None,
+            resume: None,
+        }
+    }
+}
+
+// the coordinator after the run holds the results
+pub struct Outcome {
+    pub coordinator: Coordinator,
+    pub solve_time: u128, //milliseconds in the coordinator loop
+}
+
+impl Outcome {
+    pub fn get_mis_result(&self) -> HashSet<usize> {
+        self.coordinator.get_mis_result()
+    }
+
+    pub fn round(&self) -> usize {
+        self.coordinator.round
+    }
+
+    // at the snapshot or the time limit
+    pub fn undecided(&self) -> Vec<usize> {
+        self.coordinator.residual.clone().unwrap_or_default()
+    }
+}
+
+pub fn run_threaded(graph: &[Vec<usize>], weights: &[f32], config: &RunConfig) -> Outcome {
+    let (central_sender, central_receiver) = channel();
+    let mut node_list: Vec<Node> = vec![];
+    let mut sender_list: Vec<Sender<Message>> = vec![];
+    for (i, weight) in weights.iter().enumerate() {
+        let (mut node, sender_to_node) = Node::new(i, central_sender.clone());
+        node.seed(config.seed);
+        node.set_algorithm(config.algorithm);
+        node.set_desire(config.desire);
+        node.set_weight(*weight);
+        if let Some(Some(strategy)) = config.strategies.get(i) {
+            node.set_strategy(*strategy);
+        }
+        if let Some(tracer) = config.tracer.as_ref() {
+            node.set_tracer(tracer.clone());
+        }
+        node_list.push(node);
+        sender_list.push(sender_to_node);
+    }
+    drop(central_sender);
+    for (i, neighbors) in undirected_neighbors(graph).iter().enumerate() {
+        for n_id in neighbors.iter() {
+            node_list[i].register_neighbor(*n_id, sender_list[*n_id].clone());
+        }
+    }
+
+    // a resumed run only starts the nodes still undecided at the checkpoint
+    if let Some(checkpoint) = config.resume.as_ref() {
+        for state in checkpoint.nodes.iter() {
+            node_list[state.id].restore(state);
+        }
+        let undecided: HashSet<usize> = checkpoint.nodes.iter().map(|state| state.id).collect();
+        node_list.retain(|node| undecided.contains(&node.id));
+    }
+
+    let mut coordinator = Coordinator::new(sender_list, central_receiver);
+    if let Some(tracer) = config.tracer.as_ref() {
+        coordinator.set_tracer(tracer.clone());
+    }
+    if let Some(checkpoint) = config.resume.as_ref() {
+        coordinator.resume(checkpoint);
+    }
+    if let Some(limit) = config.time_limit {
+        coordinator.set_time_limit(limit);
+    }
+    if let Some((path, every)) = config.checkpoint.as_ref() {
+        coordinator.set_checkpoint(path, *every, Checkpoint::new(config.seed, config.algorithm, config.desire, graph, weights));
+    }
+    if let Some((round, is_stopping)) = config.snapshot {
+        coordinator.set_snapshot(round, is_stopping);
+    }
+
+    let join_handlers: Vec<_> = node_list.into_iter().map(|node| node.start()).collect();
+    let solve_now = Instant::now();
+    coordinator.start();
+    let solve_time = solve_now.elapsed().as_millis();
+    // nodes stopped in the middle of a round may still report to the coordinator, they
+    // all leave once their queue is drained, then the coordinator can drop its receiver
+    if coordinator.is_timed_out {
+        for handler in join_handlers {
+            handler.join().expect("node thread panics");
+        }
+    }
+    Outcome {
+        coordinator,
+        solve_time,
+    }
+}
@@ -0,0 +1,257 @@
+use super::node::{Node, Message, Algorithm};
+use super::coordinator::{Coordinator};
+use super::desire::{DesireConfig};
+use super::graph::{undirected_neighbors};
+use super::tracer::{TraceEvent, Endpoint, Payload};
+use rand::{Rng, SeedableRng};
+use rand::prng::XorShiftRng;
+use std::collections::{HashSet};
+use std::sync::mpsc::{Sender, Receiver, channel};
+
+// which pending delivery comes next, the delaying policies deliver in FIFO order among
+// the messages they do not hold back
+#[derive(Clone, Debug, PartialEq)]
+pub enum Policy {
+    Random, //any link, drawn with the seed
+    Fifo, //the oldest message first
+    Lifo, //the newest message first
+    Starve(usize), //messages to this node wait until nothing else can be delivered
+    DelayEdges(HashSet<(usize, usize)>), //messages on these edges, either way, wait the same
+}
+
+impl Policy {
+    // random, fifo, lifo, starve:3 or delay:1-2,4-5
+    pub fn parse(policy: &str) -> Result<Policy, String> {
+        let (name, argument) = match policy.split_once(':') {
+            Some((name, argument)) => (name, Some(argument)),
+            None => (policy, None),
+        };
+        match (name.to_lowercase().as_str(), argument) {
+            ("random", None) => Ok(Policy::Random),
+            ("fifo", None) => Ok(Policy::Fifo),
+            ("lifo", None) => Ok(Policy::Lifo),
+            ("starve", Some(id)) => id.trim().parse::<usize>()
+                .map(Policy::Starve)
+                .map_err(|_| format!("invalid node id {}", id)),
+            ("delay", Some(edges)) => {
+                let mut delayed: HashSet<(usize, usize)> = HashSet::new();
+                for edge in edges.split(',') {
+                    let ends: Vec<usize> = edge.split('-').map(|id| id.trim().parse::<usize>()).collect::<Result<_, _>>()
+                        .map_err(|_| format!("invalid edge {}", edge))?;
+                    match ends.as_slice() {
+                        [u, v] => delayed.insert(((*u).min(*v), (*u).max(*v))),
+                        _ => return Err(format!("edge {} is not u-v", edge)),
+                    };
+                }
+                Ok(Policy::DelayEdges(delayed))
+            },
+            _ => Err(format!("unknown policy {}, expect random, fifo, lifo, starve:id or delay:u-v,...", policy)),
+        }
+    }
+
+    pub fn name(&self) -> String {
+        match self {
+            Policy::Random => "random".to_string(),
+            Policy::Fifo => "fifo".to_string(),
+            Policy::Lifo => "lifo".to_string(),
+            Policy::Starve(id) => format!("starve:{}", id),
+            Policy::DelayEdges(edges) => {
+                let mut edges: Vec<&(usize, usize)> = edges.iter().collect();
+                edges.sort();
+                let edges: Vec<String> = edges.iter().map(|(u, v)| format!("{}-{}", u, v)).collect();
+                format!("delay:{}", edges.join(","))
+            },
+        }
+    }
+
+    fn is_held_back(&self, from: Endpoint, to: Endpoint) -> bool {
+        match (self, from, to) {
+            (Policy::Starve(id), _, Endpoint::Node(to)) => *id == to,
+            (Policy::DelayEdges(edges), Endpoint::Node(from), Endpoint::Node(to)) => edges.contains(&(from.min(to), from.max(to))),
+            _ => false,
+        }
+    }
+}
+
+// a message sent but not delivered yet, seq orders them by sending time
+#[derive(Clone, Debug, PartialEq)]
+pub struct InFlight {
+    pub seq: usize,
+    pub from: Endpoint,
+    pub to: Endpoint,
+    pub payload: Payload,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Outcome {
+    Finished,
+    Deadlock, //nothing to deliver and the coordinator cannot move on
+}
+
+impl Outcome {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Outcome::Finished => "finished",
+            Outcome::Deadlock => "deadlock",
+        }
+    }
+}
+
+// single threaded asynchronous execution: the nodes and the coordinator only act when
+// the scheduler delivers them a message, so a run only depends on the seed and the
+// policy, messages of one link stay in order like in a channel
+pub struct Scheduler {
+    pub nodes: Vec<Option<Node>>,
+    pub receivers: Vec<Receiver<Message>>,
+    pub coordinator: Coordinator,
+    pub in_flight: Vec<InFlight>, //in sending order
+    pub policy: Policy,
+    pub rng: XorShiftRng,
+    pub num_delivery: usize,
+    pub max_in_flight: usize,
+    pub num_lost: usize, //delivered to a node that had left
+    pub num_seq: usize,
+}
+
+impl Scheduler {
+    pub fn new(graph: &[Vec<usize>], weights: &[f32], algorithm: Algorithm, seed: u64, policy: Policy) -> Scheduler {
+        let (central_sender, central_receiver) = channel();
+        let mut node_list: Vec<Node> = vec![];
+        let mut sender_list: Vec<Sender<Message>> = vec![];
+        let mut receivers: Vec<Receiver<Message>> = vec![];
+        for (i, weight) in weights.iter().enumerate() {
+            let (mut node, sender_to_node) = Node::new(i, central_sender.clone());
+            node.seed(seed);
+            node.set_algorithm(algorithm);
+            node.set_weight(*weight);
+            receivers.push(node.receiver.take().unwrap());
+            node_list.push(node);
+            sender_list.push(sender_to_node);
+        }
+        for (i, neighbors) in undirected_neighbors(graph).iter().enumerate() {
+            for n_id in neighbors.iter() {
+                node_list[i].register_neighbor(*n_id, sender_list[*n_id].clone());
+            }
+        }
+        Scheduler {
+            nodes: node_list.into_iter().map(Some).collect(),
+            receivers,
+            coordinator: Coordinator::new(sender_list, central_receiver),
+            in_flight: vec![],
+            policy,
+            rng: XorShiftRng::seed_from_u64(!seed),
+            num_delivery: 0,
+            max_in_flight: 0,
+            num_lost: 0,
+            num_seq: 0,
+        }
+    }
+
+    pub fn set_tracer(&mut self, tracer: Sender<TraceEvent>) {
+        for node in self.nodes.iter_mut().flatten() {
+            node.set_tracer(tracer.clone());
+        }
+        self.coordinator.set_tracer(tracer);
+    }
+
+    pub fn set_desire(&mut self, desire: DesireConfig) {
+        for node in self.nodes.iter_mut().flatten() {
+            node.set_desire(desire);
+        }
+    }
+
+    fn push(&mut self, from: Endpoint, to: Endpoint, payload: Payload) {
+        self.in_flight.push(InFlight { seq: self.num_seq, from, to, payload });
+        self.num_seq += 1;
+    }
+
+    fn collect_sent(&mut self) {
+        for i in 0..self.receivers.len() {
+            let sent: Vec<Message> = self.receivers[i].try_iter().collect();
+            for message in sent {
+                let from = match message.sender_id() {
+                    Some(id) => Endpoint::Node(id),
+                    None => Endpoint::Coordinator,
+                };
+                self.push(from, Endpoint::Node(i), Payload::Node(message));
+            }
+        }
+        let sent: Vec<_> = self.coordinator.central_receiver.try_iter().collect();
+        for central_message in sent {
+            self.push(Endpoint::Node(central_message.sender_id()), Endpoint::Coordinator, Payload::Central(central_message));
+        }
+        self.max_in_flight = self.max_in_flight.max(self.in_flight.len());
+    }
+
+    // the oldest message of every link, in sending order
+    pub fn deliverable(&self) -> Vec<usize> {
+        let mut links: HashSet<(Endpoint, Endpoint)> = HashSet::new();
+        (0..self.in_flight.len())
+            .filter(|index| links.insert((self.in_flight[*index].from, self.in_flight[*index].to)))
+            .collect()
+    }
+
+    fn choose(&mut self, deliverable: &[usize]) -> usize {
+        match &self.policy {
+            Policy::Random => deliverable[self.rng.gen_range(0, deliverable.len())],
+            Policy::Fifo => deliverable[0],
+            Policy::Lifo => *deliverable.last().unwrap(),
+            policy => deliverable.iter().cloned()
+                .find(|index| !policy.is_held_back(self.in_flight[*index].from, self.in_flight[*index].to))
+                .unwrap_or(deliverable[0]),
+        }
+    }
+
+    // hands one message to its receiver, the receiver of a node that left the network is
+    // kept so that sending to it never fails, which would make deliveries to different
+    // nodes depend on each other, and its messages are lost on delivery
+    pub fn deliver(&mut self, index: usize) {
+        let in_flight = self.in_flight.remove(index);
+        self.num_delivery += 1;
+        match (in_flight.to, in_flight.payload) {
+            (Endpoint::Node(id), Payload::Node(message)) => {
+                let is_staying = match self.nodes[id].as_mut() {
+                    Some(node) => node.handle_message(message),
+                    None => {
+                        self.num_lost += 1;
+                        return;
+                    },
+                };
+                if !is_staying {
+                    self.nodes[id] = None;
+                }
+            },
+            (_, Payload::Central(central_message)) => self.coordinator.handle_central_message(central_message),
+            (Endpoint::Coordinator, Payload::Node(_)) => unreachable!(),
+        }
+    }
+
+    pub fn link(&self, index: usize) -> (Endpoint, Endpoint) {
+        (self.in_flight[index].from, self.in_flight[index].to)
+    }
+
+    // the coordinator moves on as soon as it can, like its thread does, it only can after
+    // a message was delivered to it
+    pub fn settle(&mut self) {
+        self.collect_sent();
+        while !self.coordinator.is_finished() && self.coordinator.advance() {
+            self.collect_sent();
+        }
+    }
+
+    pub fn run(&mut self) -> Outcome {
+        loop {
+            self.settle();
+            if self.coordinator.is_finished() {
+                self.coordinator.finish();
+                return Outcome::Finished;
+            }
+            let deliverable = self.deliverable();
+            if deliverable.is_empty() {
+                return Outcome::Deadlock;
+            }
+            let index = self.choose(&deliverable);
+            self.deliver(index);
+        }
+    }
+}
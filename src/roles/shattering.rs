@@ -0,0 +1,79 @@
+use super::deterministic::{DeterministicMis};
+use super::graph::{undirected_neighbors};
+use std::collections::{BTreeMap, HashSet, VecDeque};
+
+// the subgraph induced by the nodes still undecided after some rounds, randomized mis
+// algorithms shatter the graph so that its components are small
+pub struct ResidualGraph {
+    pub nodes: Vec<usize>, //sorted ids in the original graph
+    pub neighbors: Vec<Vec<usize>>, //indices into nodes
+    pub components: Vec<Vec<usize>>, //original ids, largest first
+}
+
+impl ResidualGraph {
+    pub fn new(graph: &[Vec<usize>], residual: &[usize]) -> ResidualGraph {
+        let mut nodes = residual.to_vec();
+        nodes.sort();
+        let index: BTreeMap<usize, usize> = nodes.iter().enumerate().map(|(i, id)| (*id, i)).collect();
+        let all_neighbors = undirected_neighbors(graph);
+        let neighbors: Vec<Vec<usize>> = nodes.iter()
+            .map(|id| all_neighbors[*id].iter().filter_map(|n_id| index.get(n_id).cloned()).collect())
+            .collect();
+        let mut residual_graph = ResidualGraph {
+            nodes,
+            neighbors,
+            components: vec![],
+        };
+        residual_graph.components = residual_graph.find_components();
+        residual_graph
+    }
+
+    fn find_components(&self) -> Vec<Vec<usize>> {
+        let mut is_visited = vec![false; self.nodes.len()];
+        let mut components: Vec<Vec<usize>> = vec![];
+        for start in 0..self.nodes.len() {
+            if is_visited[start] {
+                continue;
+            }
+            let mut component: Vec<usize> = vec![];
+            let mut queue: VecDeque<usize> = VecDeque::new();
+            is_visited[start] = true;
+            queue.push_back(start);
+            while let Some(u) = queue.pop_front() {
+                component.push(self.nodes[u]);
+                for v in self.neighbors[u].iter() {
+                    if !is_visited[*v] {
+                        is_visited[*v] = true;
+                        queue.push_back(*v);
+                    }
+                }
+            }
+            component.sort();
+            components.push(component);
+        }
+        components.sort_by(|a, b| b.len().cmp(&a.len()).then(a.cmp(b)));
+        components
+    }
+
+    pub fn largest_component(&self) -> usize {
+        self.components.first().map_or(0, |c| c.len())
+    }
+
+    // component size, number of components of that size
+    pub fn size_distribution(&self) -> BTreeMap<usize, usize> {
+        let mut distribution: BTreeMap<usize, usize> = BTreeMap::new();
+        for component in self.components.iter() {
+            *distribution.entry(component.len()).or_insert(0) += 1;
+        }
+        distribution
+    }
+
+    // the residual nodes have no neighbor in the mis yet, so any mis of the residual
+    // graph completes it, returns the mis in original ids with the rounds it took
+    pub fn finish_deterministic(&self) -> (HashSet<usize>, usize) {
+        let mut solver = DeterministicMis::new(&self.neighbors);
+        solver.run();
+        let mis = solver.get_mis_result().into_iter().map(|i| self.nodes[i]).collect();
+        (mis, solver.color_round + solver.mis_round)
+    }
+}
@@ -0,0 +1,90 @@
+use super::creater::{Creater};
+use super::graph::{undirected_edges};
+use std::collections::{BTreeSet};
+use std::io::{Result};
+
+// a failing graph made smaller by removing nodes and edges and resetting weights to 1
+// as long as it keeps failing
+#[derive(Clone, Debug, PartialEq)]
+pub struct Shrinker {
+    pub num_node: usize,
+    pub edges: BTreeSet<(usize, usize)>, //smaller id first
+    pub weights: Vec<f32>,
+}
+
+impl Shrinker {
+    pub fn new(graph: &[Vec<usize>], weights: &[f32]) -> Shrinker {
+        Shrinker {
+            num_node: graph.len(),
+            edges: undirected_edges(graph).into_iter().collect(),
+            weights: weights.to_vec(),
+        }
+    }
+
+    // every edge is listed once, at its smaller end
+    pub fn get_graph(&self) -> Vec<Vec<usize>> {
+        let mut graph: Vec<Vec<usize>> = vec![vec![]; self.num_node];
+        for (u, v) in self.edges.iter() {
+            graph[*u].push(*v);
+        }
+        graph
+    }
+
+    // the nodes above the removed one move down to keep the ids contiguous
+    fn without_node(&self, id: usize) -> Shrinker {
+        let new_id = |u: usize| if u > id { u - 1 } else { u };
+        Shrinker {
+            num_node: self.num_node - 1,
+            edges: self.edges.iter().filter(|(u, v)| *u != id && *v != id).map(|(u, v)| (new_id(*u), new_id(*v))).collect(),
+            weights: (0..self.num_node).filter(|u| *u != id).map(|u| self.weights[u]).collect(),
+        }
+    }
+
+    fn without_edge(&self, edge: &(usize, usize)) -> Shrinker {
+        let mut shrunk = self.clone();
+        shrunk.edges.remove(edge);
+        shrunk
+    }
+
+    fn without_weight(&self, id: usize) -> Shrinker {
+        let mut shrunk = self.clone();
+        shrunk.weights[id] = 1.0;
+        shrunk
+    }
+
+    // smaller graphs first: without one node, then without one edge, then with one
+    // weight less
+    fn candidates(&self) -> Vec<Shrinker> {
+        let mut candidates: Vec<Shrinker> = vec![];
+        for id in (0..self.num_node).rev() {
+            candidates.push(self.without_node(id));
+        }
+        for edge in self.edges.iter() {
+            candidates.push(self.without_edge(edge));
+        }
+        for id in (0..self.num_node).filter(|id| self.weights[*id] != 1.0) {
+            candidates.push(self.without_weight(id));
+        }
+        candidates
+    }
+
+    // keeps the first smaller graph that still fails until none does, the result fails
+    // and loses that when any node, edge or weight goes, returns the number of steps
+    pub fn shrink<F: FnMut(&[Vec<usize>], &[f32]) -> bool>(&mut self, mut is_failing: F) -> usize {
+        let mut num_step = 0;
+        while let Some(smaller) = self.candidates().into_iter().find(|candidate| is_failing(&candidate.get_graph(), &candidate.weights)) {
+            *self = smaller;
+            num_step += 1;
+        }
+        num_step
+    }
+
+    // as an adjacency file that load_graph and the -g option read back
+    pub fn store(&self, path: &str) -> Result<()> {
+        let creater = Creater {
+            graph: self.get_graph(),
+            weights: self.weights.clone(),
+        };
+        creater.store_graph(path.to_string())
+    }
+}
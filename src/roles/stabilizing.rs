@@ -0,0 +1,172 @@
+use super::coordinator::{RoundState};
+use super::graph::{undirected_neighbors};
+use super::node::{node_seed};
+use rand::{Rng, SeedableRng};
+use rand::prng::XorShiftRng;
+use std::collections::{HashSet};
+
+// desire levels below this or above 1/2 are corrupted and reset to 1/2
+const MIN_DESIRE_LEVEL: f32 = 1.0 / 1048576.0;
+
+// everything a node keeps between rounds, all of it may be corrupted
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct StabilizingState {
+    pub is_in_mis: bool,
+    pub desire_level: f32,
+    pub has_mis_neighbor: bool, //seen in the last round
+}
+
+// self-stabilizing mis in synchronous rounds, every round each node sends its state to
+// its neighbors and applies the local rules:
+// 1. a node in the mis next to a mis node of larger id leaves
+// 2. a node that saw no mis neighbor last round marks with its desire level, and joins
+//    when no neighbor is in the mis or marked
+// 3. such a node halves its desire level when its neighbors without mis neighbor desire
+//    2 or more in total, doubles it up to 1/2 otherwise, like Luby's nodes
+// once the mis is valid no rule changes it, whatever the other variables hold
+pub struct StabilizingNode {
+    pub id: usize,
+    pub state: StabilizingState,
+    pub is_marked: bool,
+    pub rng: XorShiftRng,
+}
+
+impl StabilizingNode {
+    pub fn new(id: usize, seed: u64) -> StabilizingNode {
+        StabilizingNode {
+            id,
+            state: StabilizingState { is_in_mis: false, desire_level: 0.5, has_mis_neighbor: false },
+            is_marked: false,
+            rng: XorShiftRng::seed_from_u64(node_seed(seed, id)),
+        }
+    }
+
+    pub fn corrupt(&mut self, rng: &mut XorShiftRng) {
+        self.state = StabilizingState {
+            is_in_mis: rng.gen(),
+            desire_level: rng.gen_range(0.0, 1.0),
+            has_mis_neighbor: rng.gen(),
+        };
+    }
+
+    fn is_candidate(&self) -> bool {
+        !self.state.is_in_mis && !self.state.has_mis_neighbor
+    }
+
+    // what the node sends in this round, the desire level only counts for candidates
+    pub fn broadcast(&mut self) -> (bool, bool, f32) {
+        if !(MIN_DESIRE_LEVEL..=0.5).contains(&self.state.desire_level) {
+            self.state.desire_level = 0.5;
+        }
+        self.is_marked = self.is_candidate() && self.rng.gen_range(0.0, 1.0) < self.state.desire_level;
+        let desire_level = if self.is_candidate() { self.state.desire_level } else { 0.0 };
+        (self.state.is_in_mis, self.is_marked, desire_level)
+    }
+
+    // neighbor states as id, is in mis, is marked, desire level
+    pub fn apply_rules(&mut self, received: &[(usize, bool, bool, f32)]) {
+        if self.is_candidate() {
+            let effective_degree: f32 = received.iter().map(|(_, _, _, desire_level)| desire_level).sum();
+            if effective_degree >= 2.0 {
+                self.state.desire_level = (self.state.desire_level / 2.0).max(MIN_DESIRE_LEVEL);
+            } else {
+                self.state.desire_level = (2.0 * self.state.desire_level).min(0.5);
+            }
+        }
+        let has_mis_neighbor = received.iter().any(|(_, is_in_mis, _, _)| *is_in_mis);
+        if self.state.is_in_mis {
+            if received.iter().any(|(id, is_in_mis, _, _)| *is_in_mis && *id > self.id) {
+                self.state.is_in_mis = false;
+            }
+        } else if self.is_marked && !has_mis_neighbor && !received.iter().any(|(_, _, is_marked, _)| *is_marked) {
+            self.state.is_in_mis = true;
+        }
+        self.state.has_mis_neighbor = has_mis_neighbor;
+    }
+}
+
+pub struct StabilizingSimulator {
+    pub neighbors: Vec<Vec<usize>>,
+    pub nodes: Vec<StabilizingNode>,
+    pub round: usize,
+    pub num_message: usize,
+    pub decide_round: Vec<Option<usize>>, //last round the node changed its membership
+    pub history: Vec<RoundState>,
+    pub fault_rng: XorShiftRng,
+}
+
+impl StabilizingSimulator {
+    pub fn new(graph: &[Vec<usize>], seed: u64) -> StabilizingSimulator {
+        let neighbors = undirected_neighbors(graph);
+        StabilizingSimulator {
+            neighbors,
+            nodes: (0..graph.len()).map(|id| StabilizingNode::new(id, seed)).collect(),
+            round: 0,
+            num_message: 0,
+            decide_round: vec![None; graph.len()],
+            history: vec![],
+            fault_rng: XorShiftRng::seed_from_u64(!seed),
+        }
+    }
+
+    // transient faults: each node is corrupted with this probability, returns how many were
+    pub fn inject_faults(&mut self, rate: f64) -> usize {
+        let mut num_corrupted = 0;
+        for node in self.nodes.iter_mut() {
+            if self.fault_rng.gen_range(0.0, 1.0) < rate {
+                node.corrupt(&mut self.fault_rng);
+                num_corrupted += 1;
+            }
+        }
+        num_corrupted
+    }
+
+    pub fn is_stable(&self) -> bool {
+        (0..self.nodes.len()).all(|id| {
+            let has_mis_neighbor = self.neighbors[id].iter().any(|n_id| self.nodes[*n_id].state.is_in_mis);
+            self.nodes[id].state.is_in_mis != has_mis_neighbor
+        })
+    }
+
+    pub fn step(&mut self) {
+        let mut round_state = RoundState::new(self.round);
+        let sent: Vec<(bool, bool, f32)> = self.nodes.iter_mut().map(|n| n.broadcast()).collect();
+        for (id, (_, is_marked, _)) in sent.iter().enumerate() {
+            self.num_message += self.neighbors[id].len();
+            if *is_marked {
+                round_state.marked.push(id);
+            }
+        }
+        for id in 0..self.nodes.len() {
+            let received: Vec<(usize, bool, bool, f32)> = self.neighbors[id].iter()
+                .map(|n_id| (*n_id, sent[*n_id].0, sent[*n_id].1, sent[*n_id].2))
+                .collect();
+            let was_in_mis = self.nodes[id].state.is_in_mis;
+            self.nodes[id].apply_rules(&received);
+            match (was_in_mis, self.nodes[id].state.is_in_mis) {
+                (false, true) => round_state.joined.push(id),
+                (true, false) => round_state.removed.push(id),
+                _ => continue,
+            }
+            self.decide_round[id] = Some(self.round);
+        }
+        self.history.push(round_state);
+        self.round += 1;
+    }
+
+    // returns the rounds it took, None when still unstable after max_round rounds
+    pub fn stabilize(&mut self, max_round: usize) -> Option<usize> {
+        let start = self.round;
+        while !self.is_stable() {
+            if self.round - start >= max_round {
+                return None;
+            }
+            self.step();
+        }
+        Some(self.round - start)
+    }
+
+    pub fn get_mis_result(&self) -> HashSet<usize> {
+        self.nodes.iter().filter(|n| n.state.is_in_mis).map(|n| n.id).collect()
+    }
+}
@@ -0,0 +1,100 @@
+use super::dynamic::{Update};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write, Result, Error, ErrorKind};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Operation {
+    Update(Update),
+    Query, //report the current mis
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TimedOperation {
+    pub time: u64,
+    pub operation: Operation,
+}
+
+// a text file of timestamped operations, one per line, times never decrease and the
+// operations sharing a time form a batch, # starts a comment:
+//   0 add-edge 3 7
+//   0 remove-edge 1 2
+//   1 add-node 12 3 5 9    new node 12, the next free id, with its neighbors
+//   1 remove-node 4
+//   1 query
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct UpdateStream {
+    pub operations: Vec<TimedOperation>,
+}
+
+impl UpdateStream {
+    pub fn load(filename: &str) -> Result<UpdateStream> {
+        let f = BufReader::new(File::open(filename)?);
+        let mut lines: Vec<String> = vec![];
+        for line in f.lines() {
+            lines.push(line?);
+        }
+        UpdateStream::parse(&lines.join("\n"))
+    }
+
+    pub fn parse(text: &str) -> Result<UpdateStream> {
+        let mut operations: Vec<TimedOperation> = vec![];
+        for (number, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap();
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            if tokens.is_empty() {
+                continue;
+            }
+            let invalid = |reason: &str| Error::new(ErrorKind::InvalidData, format!("line {}: {}, {}", number + 1, reason, line.trim()));
+            if tokens.len() < 2 {
+                return Err(invalid("missing operation"));
+            }
+            let time = tokens[0].parse::<u64>().map_err(|_| invalid("invalid time"))?;
+            if operations.last().is_some_and(|last| last.time > time) {
+                return Err(invalid("time goes backwards"));
+            }
+            let mut ids: Vec<usize> = vec![];
+            for token in tokens[2..].iter() {
+                ids.push(token.parse::<usize>().map_err(|_| invalid("invalid node id"))?);
+            }
+            let operation = match (tokens[1], ids.as_slice()) {
+                ("add-edge", [u, v]) => Operation::Update(Update::AddEdge(*u, *v)),
+                ("remove-edge", [u, v]) => Operation::Update(Update::RemoveEdge(*u, *v)),
+                ("add-node", [id, dsts @ ..]) => Operation::Update(Update::AddNode(*id, dsts.to_vec())),
+                ("remove-node", [id]) => Operation::Update(Update::RemoveNode(*id)),
+                ("query", []) => Operation::Query,
+                ("add-edge", _) | ("remove-edge", _) | ("add-node", _) | ("remove-node", _) | ("query", _) => {
+                    return Err(invalid("wrong number of node ids"));
+                },
+                _ => return Err(invalid("unknown operation")),
+            };
+            operations.push(TimedOperation { time, operation });
+        }
+        Ok(UpdateStream { operations })
+    }
+
+    pub fn store(&self, filename: &str) -> Result<()> {
+        let mut f = BufWriter::new(File::create(filename)?);
+        for timed in self.operations.iter() {
+            write!(f, "{} ", timed.time)?;
+            match &timed.operation {
+                Operation::Update(update) => write!(f, "{}", update)?,
+                Operation::Query => write!(f, "query")?,
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+
+    // consecutive operations with the same time
+    pub fn batches(&self) -> Vec<&[TimedOperation]> {
+        let mut batches: Vec<&[TimedOperation]> = vec![];
+        let mut start = 0;
+        for i in 1..=self.operations.len() {
+            if i == self.operations.len() || self.operations[i].time != self.operations[start].time {
+                batches.push(&self.operations[start..i]);
+                start = i;
+            }
+        }
+        batches
+    }
+}
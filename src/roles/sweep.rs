@@ -0,0 +1,134 @@
+use super::creater::{Creater};
+use super::desire::{DesireConfig};
+use super::node::{Algorithm};
+use super::runner::{RunConfig, run_threaded};
+use super::verifier::{Verifier};
+use rand::{SeedableRng};
+use rand::prng::XorShiftRng;
+
+// graphs the desire parameters are tuned on, case i of a family runs with seed + i
+pub struct Family {
+    pub name: String,
+    pub graphs: Vec<(Vec<Vec<usize>>, Vec<f32>)>, //graph, weights
+    pub num_case: usize,
+    pub seed: u64,
+}
+
+impl Family {
+    // a new random graph for every case, with random weights up to max_weight if given
+    pub fn generate(num_node: usize, num_degree: usize, num_case: usize, max_weight: Option<usize>, seed: u64) -> Family {
+        let mut graphs = vec![];
+        for case in 0..num_case {
+            let mut rng = XorShiftRng::seed_from_u64(seed.wrapping_add(case as u64));
+            let mut creater = Creater::new();
+            let graph = creater.generate_with(num_node, num_degree, &mut rng);
+            if let Some(max_weight) = max_weight {
+                creater.random_weights_with(max_weight, &mut rng);
+            }
+            graphs.push((graph, creater.weights));
+        }
+        Family {
+            name: format!("n{}_d{}", num_node, num_degree),
+            graphs,
+            num_case,
+            seed,
+        }
+    }
+
+    // one graph, the cases only differ by their seed
+    pub fn repeat(name: &str, graph: &[Vec<usize>], weights: &[f32], num_case: usize, seed: u64) -> Family {
+        Family {
+            name: name.to_string(),
+            graphs: vec![(graph.to_vec(), weights.to_vec())],
+            num_case,
+            seed,
+        }
+    }
+
+    pub fn case(&self, case: usize) -> (&[Vec<usize>], &[f32], u64) {
+        let (graph, weights) = &self.graphs[case % self.graphs.len()];
+        (graph, weights, self.seed.wrapping_add(case as u64))
+    }
+}
+
+// rounds of the cases of a family under one setting, a run stopped at the round bound
+// counts the bound
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Measure {
+    pub num_run: usize,
+    pub total_round: usize,
+    pub max_round: usize,
+    pub num_unfinished: usize,
+    pub num_invalid: usize, //finished without an mis
+    pub total_mis: usize,
+}
+
+impl Measure {
+    pub fn mean_round(&self) -> f64 {
+        self.total_round as f64 / self.num_run.max(1) as f64
+    }
+
+    pub fn mean_mis(&self) -> f64 {
+        self.total_mis as f64 / self.num_run.max(1) as f64
+    }
+
+    // the runs of both, for a total over families
+    pub fn merge(&mut self, other: &Measure) {
+        self.num_run += other.num_run;
+        self.total_round += other.total_round;
+        self.max_round = self.max_round.max(other.max_round);
+        self.num_unfinished += other.num_unfinished;
+        self.num_invalid += other.num_invalid;
+        self.total_mis += other.total_mis;
+    }
+
+    // unfinished runs first, then mean rounds
+    pub fn is_better(&self, other: &Measure) -> bool {
+        (self.num_unfinished + self.num_invalid, self.total_round) < (other.num_unfinished + other.num_invalid, other.total_round)
+    }
+}
+
+pub fn measure(family: &Family, algorithm: Algorithm, desire: DesireConfig, max_round: usize) -> Measure {
+    let mut measure = Measure::default();
+    for case in 0..family.num_case {
+        let (graph, weights, seed) = family.case(case);
+        let mut config = RunConfig::new(algorithm, seed);
+        config.desire = desire;
+        config.snapshot = Some((max_round, true));
+        let outcome = run_threaded(graph, weights, &config);
+        let (mis, round, undecided) = (outcome.get_mis_result(), outcome.round(), outcome.undecided());
+        measure.num_run += 1;
+        measure.total_round += round;
+        measure.max_round = measure.max_round.max(round);
+        measure.total_mis += mis.len();
+        if !undecided.is_empty() {
+            measure.num_unfinished += 1;
+        } else if !Verifier::new(&mis, graph).verify() {
+            measure.num_invalid += 1;
+        }
+    }
+    measure
+}
+
+// every combination of the values given for some parameters, the others keep their base
+// value, an axis is key=v1,v2,...; returns the valid settings and the number of invalid
+// ones left out
+pub fn grid(base: DesireConfig, axes: &[&str]) -> Result<(Vec<DesireConfig>, usize), String> {
+    let mut settings = vec![base];
+    for axis in axes.iter() {
+        let (key, values) = axis.split_once('=').ok_or_else(|| format!("sweep axis {} is not key=v1,v2,...", axis))?;
+        let mut extended = vec![];
+        for setting in settings.iter() {
+            for value in values.split(',') {
+                let mut desire = *setting;
+                desire.set(key, value)?;
+                extended.push(desire);
+            }
+        }
+        settings = extended;
+    }
+    let num_setting = settings.len();
+    settings.retain(|desire| desire.check().is_ok());
+    let num_invalid = num_setting - settings.len();
+    Ok((settings, num_invalid))
+}
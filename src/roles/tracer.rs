@@ -0,0 +1,129 @@
+use super::node::{Message, CentralMessage, Algorithm};
+use super::coordinator::{Stage};
+use super::byzantine::{Strategy};
+use super::desire::{DesireConfig};
+use serde::{Serialize, Deserialize};
+use bincode::{serialize_into, deserialize_from};
+
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write, Result, Error, ErrorKind};
+use std::sync::mpsc::{Sender, Receiver, channel};
+use std::thread::{self, JoinHandle};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Endpoint {
+    Coordinator,
+    Node(usize),
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Payload {
+    Node(Message),
+    Central(CentralMessage),
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum TraceEvent {
+    // a message taken out of the receiver channel, recorded before it is handled
+    Delivery {
+        round: usize, //round of the receiver
+        from: Endpoint,
+        to: Endpoint,
+        payload: Payload,
+    },
+    Stage {
+        round: usize,
+        stage: Stage,
+    },
+    End(Vec<usize>), //sorted mis
+}
+
+// everything needed to re-execute the run
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TraceHeader {
+    pub seed: u64,
+    pub algorithm: Algorithm,
+    pub graph: Vec<Vec<usize>>,
+    pub weights: Vec<f32>,
+    pub stop_round: Option<usize>, //the run stopped after these rounds
+    pub strategies: Vec<Option<Strategy>>, //of the byzantine nodes
+    pub desire: DesireConfig,
+}
+
+impl Payload {
+    // the coordinator stage in which such a message is exchanged
+    pub fn stage(&self) -> Stage {
+        match self {
+            Payload::Node(Message::Start(_)) | Payload::Node(Message::Checkpoint) => Stage::Start,
+            Payload::Node(Message::Request(_)) | Payload::Node(Message::Response(_)) => Stage::Round1,
+            Payload::Node(Message::Bit(_)) | Payload::Node(Message::TryColor(_)) => Stage::Round1,
+            Payload::Node(Message::Propose(_)) | Payload::Node(Message::Accept(_)) | Payload::Node(Message::Choose(_)) => Stage::Round1,
+            Payload::Node(Message::StartRound2) | Payload::Node(Message::JoinedMIS(_)) => Stage::Round2,
+            Payload::Node(Message::RemoveNeighbors(_)) => Stage::Reconfigure,
+            Payload::Central(CentralMessage::Round1Complete(_)) => Stage::Round1,
+            Payload::Central(CentralMessage::Step(_)) | Payload::Central(CentralMessage::Finish(_)) => Stage::Round2,
+            Payload::Central(CentralMessage::ReconfigComplete(_)) => Stage::Reconfigure,
+            Payload::Central(CentralMessage::State(_)) => Stage::Start,
+        }
+    }
+}
+
+impl TraceEvent {
+    pub fn describe(&self) -> String {
+        match self {
+            TraceEvent::Delivery { round, from, to, payload } => {
+                format!("round {} {:?} {:?} -> {:?} {:?}", round, payload.stage(), from, to, payload)
+            },
+            TraceEvent::Stage { round, stage } => format!("round {} coordinator enters {:?}", round, stage),
+            TraceEvent::End(mis) => format!("end mis {:?}", mis),
+        }
+    }
+}
+
+// events from every thread go through one channel, so the file keeps the order
+// in which they were recorded
+pub struct Tracer {
+    pub sender: Sender<TraceEvent>,
+    handle: JoinHandle<Result<()>>,
+}
+
+impl Tracer {
+    pub fn create(filename: &str, header: &TraceHeader) -> Result<Tracer> {
+        let mut f = BufWriter::new(File::create(filename)?);
+        serialize_into(&mut f, header).map_err(Error::other)?;
+        let (sender, receiver): (Sender<TraceEvent>, Receiver<TraceEvent>) = channel();
+        let handle = thread::spawn(move || {
+            for event in receiver.iter() {
+                let is_end = matches!(event, TraceEvent::End(_));
+                serialize_into(&mut f, &event).map_err(Error::other)?;
+                if is_end {
+                    break;
+                }
+            }
+            f.flush()
+        });
+        Ok(Tracer { sender, handle })
+    }
+
+    // nodes still running after the End event are not recorded
+    pub fn finish(self) -> Result<()> {
+        drop(self.sender);
+        self.handle.join().expect("trace writer panics")
+    }
+}
+
+pub fn read_trace(filename: &str) -> Result<(TraceHeader, Vec<TraceEvent>)> {
+    let mut f = BufReader::new(File::open(filename)?);
+    let header: TraceHeader = deserialize_from(&mut f).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+    let mut events: Vec<TraceEvent> = vec![];
+    loop {
+        match deserialize_from(&mut f) {
+            Ok(event) => events.push(event),
+            Err(e) => match *e {
+                bincode::ErrorKind::Io(ref io) if io.kind() == ErrorKind::UnexpectedEof => break,
+                _ => return Err(Error::new(ErrorKind::InvalidData, e)),
+            },
+        }
+    }
+    Ok((header, events))
+}
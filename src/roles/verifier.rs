@@ -1,4 +1,11 @@
-use std::collections::{HashSet};
+use super::graph::{undirected_neighbors, undirected_edges};
+use std::collections::{HashSet, VecDeque};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Violation {
+    Independence(usize, usize), //two adjacent mis nodes
+    Maximality(usize), //node neither in the mis nor adjacent to it
+}
 
 pub struct Verifier {
     pub mis: HashSet<usize>,
@@ -8,11 +15,11 @@ pub struct Verifier {
 impl Verifier {
     pub fn new(
         mis: &HashSet<usize>,
-        graph: &Vec<Vec<usize>>,
+        graph: &[Vec<usize>],
     ) -> Verifier {
         Verifier {
             mis: mis.clone(),
-            graph: graph.clone(),
+            graph: graph.to_vec(),
         } 
     }
 
@@ -28,21 +35,234 @@ impl Verifier {
     }
 
     pub fn verify(&self) -> bool {
+        self.find_violations().is_empty()
+    }
+
+    // independence violations are reported once per edge, with the smaller id first
+    pub fn find_violations(&self) -> Vec<Violation> {
         let edge_set = self.build_edge_set();
-        for i in 0..self.graph.len() {
-            if !self.mis.contains(&i) {
-                let mut is_none_contain = true;
-                for j in self.graph[i].iter() {
-                    if self.mis.contains(j) {
-                        is_none_contain = false; 
-                    }
+        let mut violations: Vec<Violation> = vec![];
+        let mut edges: Vec<&(usize, usize)> = edge_set.iter().filter(|(i, j)| i < j).collect();
+        edges.sort();
+        for (i, j) in edges {
+            if self.mis.contains(i) && self.mis.contains(j) {
+                violations.push(Violation::Independence(*i, *j));
+            }
+        }
+        let mut is_covered: Vec<bool> = vec![false; self.graph.len()];
+        for (i, j) in edge_set.iter() {
+            if self.mis.contains(j) {
+                is_covered[*i] = true;
+            }
+        }
+        for (i, covered) in is_covered.iter().enumerate() {
+            if !self.mis.contains(&i) && !covered {
+                violations.push(Violation::Maximality(i));
+            }
+        }
+        violations
+    }
+
+    // byzantine nodes promise nothing, so only violations between honest nodes count,
+    // an honest node next to a byzantine mis node is covered
+    pub fn find_honest_violations(&self, byzantine: &HashSet<usize>) -> Vec<Violation> {
+        self.find_violations().into_iter().filter(|violation| match violation {
+            Violation::Independence(i, j) => !byzantine.contains(i) && !byzantine.contains(j),
+            Violation::Maximality(i) => !byzantine.contains(i),
+        }).collect()
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ColoringViolation {
+    Uncolored(usize),
+    Conflict(usize, usize), //two adjacent nodes with the same color
+    Palette(usize, usize), //node, color above max degree
+}
+
+// checks a (max degree + 1)-coloring: every node colored, colors below max degree + 1,
+// and no edge with the same color at both ends
+pub struct ColoringVerifier {
+    pub colors: Vec<Option<usize>>,
+    pub graph: Vec<Vec<usize>>,
+}
+
+impl ColoringVerifier {
+    pub fn new(
+        colors: &[Option<usize>],
+        graph: &[Vec<usize>],
+    ) -> ColoringVerifier {
+        ColoringVerifier {
+            colors: colors.to_vec(),
+            graph: graph.to_vec(),
+        }
+    }
+
+    pub fn max_degree(&self) -> usize {
+        undirected_neighbors(&self.graph).iter().map(|n| n.len()).max().unwrap_or(0)
+    }
+
+    pub fn num_color(&self) -> usize {
+        self.colors.iter().flatten().collect::<HashSet<&usize>>().len()
+    }
+
+    pub fn verify(&self) -> bool {
+        self.find_violations().is_empty()
+    }
+
+    // conflicts are reported once per edge, with the smaller id first
+    pub fn find_violations(&self) -> Vec<ColoringViolation> {
+        let mut violations: Vec<ColoringViolation> = vec![];
+        let max_degree = self.max_degree();
+        for (i, color) in self.colors.iter().enumerate() {
+            match color {
+                None => violations.push(ColoringViolation::Uncolored(i)),
+                Some(color) if *color > max_degree => violations.push(ColoringViolation::Palette(i, *color)),
+                Some(_) => (),
+            }
+        }
+        for (i, j) in undirected_edges(&self.graph) {
+            if self.colors[i].is_some() && self.colors[i] == self.colors[j] {
+                violations.push(ColoringViolation::Conflict(i, j));
+            }
+        }
+        violations
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum MatchingViolation {
+    NotEdge(usize, usize), //matched nodes that are not adjacent
+    Asymmetric(usize), //node whose partner is matched to another node
+    Maximality(usize, usize), //edge with both ends unmatched
+}
+
+// checks that partners form a matching of the graph edges and that every edge has a
+// matched end
+pub struct MatchingVerifier {
+    pub partners: Vec<Option<usize>>,
+    pub graph: Vec<Vec<usize>>,
+}
+
+impl MatchingVerifier {
+    pub fn new(
+        partners: &[Option<usize>],
+        graph: &[Vec<usize>],
+    ) -> MatchingVerifier {
+        MatchingVerifier {
+            partners: partners.to_vec(),
+            graph: graph.to_vec(),
+        }
+    }
+
+    fn edges(&self) -> Vec<(usize, usize)> {
+        undirected_edges(&self.graph)
+    }
+
+    // matched edges with the smaller id first
+    pub fn get_matching(&self) -> Vec<(usize, usize)> {
+        self.partners.iter().enumerate()
+            .filter_map(|(i, p)| p.filter(|p| i < *p).map(|p| (i, p)))
+            .collect()
+    }
+
+    pub fn verify(&self) -> bool {
+        self.find_violations().is_empty()
+    }
+
+    pub fn find_violations(&self) -> Vec<MatchingViolation> {
+        let mut violations: Vec<MatchingViolation> = vec![];
+        let edges = self.edges();
+        let edge_set: HashSet<&(usize, usize)> = edges.iter().collect();
+        for (i, partner) in self.partners.iter().enumerate() {
+            if let Some(p) = partner {
+                if self.partners.get(*p) != Some(&Some(i)) {
+                    violations.push(MatchingViolation::Asymmetric(i));
+                } else if i < *p && !edge_set.contains(&(i, *p)) {
+                    violations.push(MatchingViolation::NotEdge(i, *p));
                 }
-                if is_none_contain {
-                    return false; 
+            }
+        }
+        for (i, j) in edges.iter() {
+            if self.partners[*i].is_none() && self.partners[*j].is_none() {
+                violations.push(MatchingViolation::Maximality(*i, *j));
+            }
+        }
+        violations
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum RulingViolation {
+    TooClose(usize, usize, usize), //two rulers and their distance, below alpha
+    TooFar(usize, Option<usize>), //node and its distance to the rulers, above beta or unreachable
+}
+
+// checks an (alpha, beta)-ruling set with breadth first searches on the graph: rulers
+// are at distance alpha or more from each other and every node is within beta of one
+pub struct RulingVerifier {
+    pub rulers: HashSet<usize>,
+    pub neighbors: Vec<Vec<usize>>,
+}
+
+impl RulingVerifier {
+    pub fn new(
+        rulers: &HashSet<usize>,
+        graph: &[Vec<usize>],
+    ) -> RulingVerifier {
+        RulingVerifier {
+            rulers: rulers.clone(),
+            neighbors: undirected_neighbors(graph),
+        }
+    }
+
+    // distances from the sources, up to the given depth
+    fn bfs(&self, sources: &[usize], depth: usize) -> Vec<Option<usize>> {
+        let mut distance: Vec<Option<usize>> = vec![None; self.neighbors.len()];
+        let mut queue: VecDeque<usize> = VecDeque::new();
+        for s in sources.iter() {
+            distance[*s] = Some(0);
+            queue.push_back(*s);
+        }
+        while let Some(u) = queue.pop_front() {
+            let d = distance[u].unwrap();
+            if d == depth {
+                continue;
+            }
+            for v in self.neighbors[u].iter() {
+                if distance[*v].is_none() {
+                    distance[*v] = Some(d + 1);
+                    queue.push_back(*v);
                 }
-            } 
+            }
         }
+        distance
+    }
+
+    pub fn verify(&self, alpha: usize, beta: usize) -> bool {
+        self.find_violations(alpha, beta).is_empty()
+    }
 
-        true 
+    // close pairs are reported once, with the smaller id first
+    pub fn find_violations(&self, alpha: usize, beta: usize) -> Vec<RulingViolation> {
+        let mut violations: Vec<RulingViolation> = vec![];
+        let mut rulers: Vec<usize> = self.rulers.iter().cloned().collect();
+        rulers.sort();
+        for u in rulers.iter() {
+            let distance = self.bfs(&[*u], alpha.saturating_sub(1));
+            for v in rulers.iter().filter(|v| *v > u) {
+                if let Some(d) = distance[*v] {
+                    violations.push(RulingViolation::TooClose(*u, *v, d));
+                }
+            }
+        }
+        let distance = self.bfs(&rulers, usize::MAX);
+        for (i, d) in distance.iter().enumerate() {
+            match d {
+                Some(d) if *d <= beta => (),
+                _ => violations.push(RulingViolation::TooFar(i, *d)),
+            }
+        }
+        violations
     }
 }
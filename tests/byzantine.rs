@@ -0,0 +1,84 @@
+// checks of the runs with liars: whatever the strategy, the honest nodes never join
+// next to each other, and what the liars break stays next to them
+use distributed_mis::roles::byzantine::{Strategy, assign_strategies, byzantine_nodes};
+use distributed_mis::roles::creater::{Creater};
+use distributed_mis::roles::graph::{undirected_neighbors};
+use distributed_mis::roles::node::{Algorithm};
+use distributed_mis::roles::runner::{RunConfig, run_threaded};
+use distributed_mis::roles::verifier::{Verifier, Violation};
+use rand::{SeedableRng};
+use rand::prng::XorShiftRng;
+use std::collections::{HashSet};
+
+const NUM_CASE: u64 = 8;
+const NUM_BYZANTINE: usize = 5;
+const MAX_ROUND: usize = 200;
+
+const STRATEGIES: [Strategy; 3] = [Strategy::AlwaysMarked, Strategy::FalseDesire, Strategy::FakeJoin];
+
+#[test]
+fn honest_nodes_under_every_strategy() {
+    for strategy in STRATEGIES.iter() {
+        for seed in 0..NUM_CASE {
+            let mut rng = XorShiftRng::seed_from_u64(seed);
+            let mut creater = Creater::new();
+            let graph = creater.generate_with(100, 5, &mut rng);
+            let algorithm = [Algorithm::Luby, Algorithm::Weighted, Algorithm::Metivier][seed as usize % 3];
+            if algorithm == Algorithm::Weighted {
+                creater.random_weights_with(100, &mut rng);
+            }
+            let mut config = RunConfig::new(algorithm, seed);
+            config.strategies = assign_strategies(graph.len(), NUM_BYZANTINE, *strategy, seed);
+            config.snapshot = Some((MAX_ROUND, true));
+            let outcome = run_threaded(&graph, &creater.weights, &config);
+            let byzantine = byzantine_nodes(&config.strategies);
+            assert_eq!(byzantine.len(), NUM_BYZANTINE);
+            let mis = outcome.get_mis_result();
+            let neighbors = undirected_neighbors(&graph);
+            let violations = Verifier::new(&mis, &graph).find_honest_violations(&byzantine);
+            for violation in violations.iter() {
+                // a lie can only keep a neighbor of the liar out of the mis
+                match violation {
+                    Violation::Maximality(id) => assert!(neighbors[*id].iter().any(|n_id| byzantine.contains(n_id)),
+                        "{:?} seed {} {:?}", strategy, seed, violation),
+                    Violation::Independence(_, _) => panic!("{:?} seed {} {:?}", strategy, seed, violation),
+                }
+            }
+            // a lied desire level only slows the neighbors down
+            if *strategy == Strategy::FalseDesire {
+                assert!(violations.is_empty(), "seed {} {:?}", seed, violations);
+                assert!(outcome.undecided().is_empty(), "seed {}", seed);
+            }
+        }
+    }
+}
+
+fn ids(ids: &[usize]) -> HashSet<usize> {
+    ids.iter().cloned().collect()
+}
+
+// violations that involve a liar are not the honest nodes' to answer for
+#[test]
+fn honest_violations_leave_out_the_liars() {
+    // 0 - 1 - 2 - 3, and 4 on its own
+    let graph = vec![vec![1], vec![2], vec![3], vec![], vec![]];
+    let byzantine = ids(&[1]);
+    let verifier = Verifier::new(&ids(&[0, 1, 3]), &graph);
+    assert_eq!(verifier.find_violations(), vec![Violation::Independence(0, 1), Violation::Maximality(4)]);
+    assert_eq!(verifier.find_honest_violations(&byzantine), vec![Violation::Maximality(4)]);
+    // the liar in the mis covers 0
+    let verifier = Verifier::new(&ids(&[1, 2, 4]), &graph);
+    assert_eq!(verifier.find_violations(), vec![Violation::Independence(1, 2)]);
+    assert!(verifier.find_honest_violations(&byzantine).is_empty());
+    let verifier = Verifier::new(&ids(&[0, 2, 3, 4]), &graph);
+    assert_eq!(verifier.find_honest_violations(&byzantine), vec![Violation::Independence(2, 3)]);
+}
+
+#[test]
+fn strategies_parse() {
+    for strategy in STRATEGIES.iter() {
+        assert_eq!(Strategy::parse(strategy.name()), Some(*strategy));
+    }
+    assert_eq!(Strategy::parse("Joined"), Some(Strategy::FakeJoin));
+    assert_eq!(Strategy::parse("silent"), None);
+}
@@ -0,0 +1,154 @@
+// checks of the dynamic repairs: the mis stays valid after every kind of update and the
+// adjustments count what actually changed, and of the update streams that drive them
+use distributed_mis::roles::creater::{Creater};
+use distributed_mis::roles::dynamic::{DynamicMis, Update, Adjustment};
+use distributed_mis::roles::node::{Algorithm};
+use distributed_mis::roles::stream::{UpdateStream, Operation};
+use rand::{Rng, SeedableRng};
+use rand::prng::XorShiftRng;
+use std::collections::{HashSet};
+
+const NUM_CASE: u64 = 16;
+
+fn adjustment(affected: usize, changed: usize, round: usize) -> Adjustment {
+    Adjustment { affected, changed, round }
+}
+
+// without edges every node is in the mis, so each step below has a single outcome
+#[test]
+fn updates_on_isolated_nodes() {
+    let graph = vec![vec![]; 4];
+    let mut dynamic = DynamicMis::new(&graph, &[1.0; 4], Algorithm::Luby, 0).unwrap();
+    assert_eq!(dynamic.initialize(), 0);
+    assert_eq!(dynamic.mis, (0..4).collect());
+    let steps = [
+        // the larger end leaves and stays covered by the other one
+        (Update::AddEdge(0, 1), adjustment(0, 1, 0), vec![0, 2, 3]),
+        // the edge was its only cover, it joins on its own
+        (Update::RemoveEdge(0, 1), adjustment(1, 1, 0), vec![0, 1, 2, 3]),
+        (Update::AddNode(4, vec![0, 1]), adjustment(0, 0, 0), vec![0, 1, 2, 3]),
+        (Update::AddNode(5, vec![]), adjustment(1, 1, 0), vec![0, 1, 2, 3, 5]),
+        // 4 is still covered by 1
+        (Update::RemoveNode(0), adjustment(0, 1, 0), vec![1, 2, 3, 5]),
+        // now 4 has no neighbor left
+        (Update::RemoveNode(1), adjustment(1, 2, 0), vec![2, 3, 4, 5]),
+    ];
+    for (update, expected, mis) in steps.iter() {
+        assert_eq!(dynamic.apply(update).as_ref(), Ok(expected), "{}", update);
+        assert_eq!(dynamic.mis, mis.iter().cloned().collect(), "{}", update);
+        assert!(dynamic.verifier().verify(), "{}", update);
+    }
+    assert_eq!(dynamic.num_node(), 4);
+}
+
+// the two nodes left of a triangle are adjacent and both uncovered, so the distributed
+// algorithm picks one of them
+#[test]
+fn departure_repaired_by_the_algorithm() {
+    let graph = vec![vec![1, 2], vec![2], vec![]];
+    for seed in 0..NUM_CASE {
+        let mut dynamic = DynamicMis::new(&graph, &[1.0; 3], Algorithm::Luby, seed).unwrap();
+        dynamic.initialize();
+        assert_eq!(dynamic.mis.len(), 1);
+        let member = *dynamic.mis.iter().next().unwrap();
+        let adjustment = dynamic.apply(&Update::RemoveNode(member)).unwrap();
+        assert_eq!((adjustment.affected, adjustment.changed), (2, 2));
+        assert!(adjustment.round >= 1);
+        assert_eq!(dynamic.mis.len(), 1);
+        assert!(dynamic.verifier().verify());
+    }
+}
+
+#[test]
+fn wrong_updates_are_errors() {
+    let graph = vec![vec![1], vec![]];
+    assert!(DynamicMis::new(&graph, &[1.0; 2], Algorithm::Coloring, 0).is_err());
+    assert!(DynamicMis::new(&graph, &[1.0; 2], Algorithm::Matching, 0).is_err());
+    let mut dynamic = DynamicMis::new(&graph, &[1.0; 2], Algorithm::Luby, 0).unwrap();
+    dynamic.initialize();
+    let mis = dynamic.mis.clone();
+    assert!(dynamic.apply(&Update::AddNode(5, vec![0])).is_err());
+    assert_eq!(dynamic.neighbors.len(), 2);
+    assert_eq!(dynamic.mis, mis);
+}
+
+// a random update of each kind in turn, on a random graph
+fn next_update(dynamic: &DynamicMis, kind: usize, rng: &mut XorShiftRng) -> Update {
+    let present: Vec<usize> = (0..dynamic.neighbors.len()).filter(|id| dynamic.contains(*id)).collect();
+    let id = present[rng.gen_range(0, present.len())];
+    match kind % 4 {
+        0 => Update::AddEdge(id, present[rng.gen_range(0, present.len())]),
+        1 => match dynamic.neighbors[id].iter().min() {
+            Some(n_id) => Update::RemoveEdge(id, *n_id),
+            None => Update::RemoveEdge(id, id),
+        },
+        2 => Update::AddNode(dynamic.neighbors.len(), (0..3).map(|_| present[rng.gen_range(0, present.len())]).collect()),
+        _ => Update::RemoveNode(id),
+    }
+}
+
+#[test]
+fn random_updates_keep_the_mis() {
+    for seed in 0..NUM_CASE {
+        let mut rng = XorShiftRng::seed_from_u64(seed);
+        let mut creater = Creater::new();
+        let graph = creater.generate_with(rng.gen_range(8, 24), 3, &mut rng);
+        let algorithm = [Algorithm::Luby, Algorithm::Weighted, Algorithm::Metivier][seed as usize % 3];
+        let mut dynamic = DynamicMis::new(&graph, &creater.weights, algorithm, seed).unwrap();
+        dynamic.initialize();
+        assert!(dynamic.verifier().verify());
+        for kind in 0..40 {
+            let update = next_update(&dynamic, kind, &mut rng);
+            let before = dynamic.mis.clone();
+            let adjustment = dynamic.apply(&update).unwrap();
+            assert!(dynamic.verifier().verify(), "seed {} {}", seed, update);
+            // every node that joined was undecided
+            let joined: HashSet<usize> = dynamic.mis.difference(&before).cloned().collect();
+            assert_eq!(adjustment.changed, dynamic.mis.symmetric_difference(&before).count(), "seed {} {}", seed, update);
+            assert!(adjustment.affected >= joined.len(), "seed {} {}", seed, update);
+            if adjustment.affected < 2 {
+                assert_eq!(adjustment.round, 0, "seed {} {}", seed, update);
+            }
+        }
+    }
+}
+
+fn parse_error(text: &str) -> String {
+    UpdateStream::parse(text).unwrap_err().to_string()
+}
+
+#[test]
+fn stream_parsing() {
+    let stream = UpdateStream::parse("# a comment\n0 add-edge 3 7\n\n0 remove-edge 1 2 # trailing\n1 add-node 12 3 5\n1 add-node 13\n2 remove-node 4\n2 query\n").unwrap();
+    let operations: Vec<(u64, Operation)> = stream.operations.iter().map(|timed| (timed.time, timed.operation.clone())).collect();
+    assert_eq!(operations, vec![
+        (0, Operation::Update(Update::AddEdge(3, 7))),
+        (0, Operation::Update(Update::RemoveEdge(1, 2))),
+        (1, Operation::Update(Update::AddNode(12, vec![3, 5]))),
+        (1, Operation::Update(Update::AddNode(13, vec![]))),
+        (2, Operation::Update(Update::RemoveNode(4))),
+        (2, Operation::Query),
+    ]);
+}
+
+#[test]
+fn malformed_streams() {
+    assert!(parse_error("0 add-edge 1 2\n3").contains("line 2: missing operation"));
+    assert!(parse_error("x add-edge 1 2").contains("line 1: invalid time"));
+    assert!(parse_error("-1 query").contains("invalid time"));
+    assert!(parse_error("0 add-edge 1 b").contains("invalid node id"));
+    assert!(parse_error("0 swap-edge 1 2").contains("unknown operation"));
+    for line in ["0 add-edge 1", "0 add-edge 1 2 3", "0 remove-edge 1", "0 add-node", "0 remove-node", "0 remove-node 1 2", "0 query 1"] {
+        assert!(parse_error(line).contains("wrong number of node ids"), "{}", line);
+    }
+    assert!(parse_error("1 query\n2 query\n1 add-edge 0 1").contains("line 3: time goes backwards"));
+}
+
+#[test]
+fn stream_batches() {
+    let stream = UpdateStream::parse("0 add-edge 0 1\n0 query\n3 remove-node 2\n7 add-edge 1 2\n7 remove-edge 0 1\n7 query").unwrap();
+    let batches: Vec<(u64, usize)> = stream.batches().iter().map(|batch| (batch[0].time, batch.len())).collect();
+    assert_eq!(batches, vec![(0, 2), (3, 1), (7, 3)]);
+    assert!(stream.batches().iter().all(|batch| batch.iter().all(|timed| timed.time == batch[0].time)));
+    assert!(UpdateStream::parse("").unwrap().batches().is_empty());
+}
@@ -0,0 +1,56 @@
+// checks that the explorer only silences its own panics: a hook installed by the test
+// still sees the panics of other threads, during and after the exploration
+use distributed_mis::roles::explorer::{Explorer};
+use distributed_mis::roles::node::{Algorithm};
+use std::panic;
+use std::sync::{Arc, Barrier};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::thread;
+
+static NUM_REPORTED: AtomicUsize = AtomicUsize::new(0);
+
+const NUM_PANIC: usize = 20;
+
+fn panic_quietly() {
+    let _ = panic::catch_unwind(|| panic!("reported"));
+}
+
+#[test]
+fn explorer_keeps_other_panics() {
+    panic::set_hook(Box::new(|_| {
+        NUM_REPORTED.fetch_add(1, Ordering::SeqCst);
+    }));
+    // the nodes of a graph without weights panic in every execution
+    let explorer = Explorer::new(&[vec![1], vec![]], &[], Algorithm::Luby, 0);
+    let exploration = explorer.explore(10);
+    assert!(exploration.num_panic > 0);
+    explorer.rerun(&[], None);
+    assert_eq!(NUM_REPORTED.load(Ordering::SeqCst), 0);
+    panic_quietly();
+    assert_eq!(NUM_REPORTED.load(Ordering::SeqCst), 1);
+
+    // the explorers keep going until this thread is done panicking
+    let barrier = Arc::new(Barrier::new(3));
+    let is_done = Arc::new(AtomicBool::new(false));
+    let explorers: Vec<thread::JoinHandle<usize>> = (0..2).map(|_| {
+        let (barrier, is_done) = (barrier.clone(), is_done.clone());
+        thread::spawn(move || {
+            let explorer = Explorer::new(&[vec![1], vec![]], &[], Algorithm::Luby, 0);
+            let mut num_panic = explorer.explore(10).num_panic;
+            barrier.wait();
+            while !is_done.load(Ordering::SeqCst) {
+                num_panic += explorer.explore(10).num_panic;
+            }
+            num_panic
+        })
+    }).collect();
+    barrier.wait();
+    for _ in 0..NUM_PANIC {
+        panic_quietly();
+    }
+    is_done.store(true, Ordering::SeqCst);
+    for explorer in explorers {
+        assert!(explorer.join().unwrap() > 0);
+    }
+    assert_eq!(NUM_REPORTED.load(Ordering::SeqCst), 1 + NUM_PANIC);
+}
@@ -0,0 +1,47 @@
+// checks of the log filter: what --log-level, --log-node and --log-round accept and
+// which records they let through
+use distributed_mis::logger::{Level, LogFilter};
+use std::collections::{HashSet};
+
+#[test]
+fn round_ranges() {
+    assert_eq!(LogFilter::parse_rounds("3"), Ok((3, 4)));
+    assert_eq!(LogFilter::parse_rounds("3..5"), Ok((3, 5)));
+    assert_eq!(LogFilter::parse_rounds("3..=5"), Ok((3, 6)));
+    assert_eq!(LogFilter::parse_rounds(" 0 ..= 0 "), Ok((0, 1)));
+    for rounds in ["", "x", "-1", "3..", "..5", "3..=", "3...5", "3..x", "3,5"] {
+        assert_eq!(LogFilter::parse_rounds(rounds), Err(format!("invalid round range {}", rounds)), "{}", rounds);
+    }
+    for rounds in ["5..3", "3..3", "5..=4"] {
+        assert_eq!(LogFilter::parse_rounds(rounds), Err(format!("empty round range {}", rounds)), "{}", rounds);
+    }
+}
+
+#[test]
+fn node_lists() {
+    let nodes: HashSet<usize> = [17, 18, 40].iter().cloned().collect();
+    assert_eq!(LogFilter::parse_nodes("17, 18,40"), Ok(nodes));
+    assert_eq!(LogFilter::parse_nodes("17"), Ok([17].iter().cloned().collect()));
+    assert_eq!(LogFilter::parse_nodes("17,x"), Err("invalid node id x".to_string()));
+    assert_eq!(LogFilter::parse_nodes("17,"), Err("invalid node id ".to_string()));
+    assert!(LogFilter::parse_nodes("").is_err());
+    assert_eq!(Level::parse("DEBUG"), Some(Level::Debug));
+    assert_eq!(Level::parse("verbose"), None);
+}
+
+#[test]
+fn filtered_records() {
+    let filter = LogFilter {
+        level: Level::Debug,
+        nodes: Some([17].iter().cloned().collect()),
+        rounds: Some(LogFilter::parse_rounds("3..=5").unwrap()),
+    };
+    assert!(filter.is_enabled(Level::Debug, Some(3), Some(17)));
+    assert!(filter.is_enabled(Level::Error, Some(5), Some(17)));
+    assert!(!filter.is_enabled(Level::Trace, Some(4), Some(17)));
+    assert!(!filter.is_enabled(Level::Info, Some(6), Some(17)));
+    assert!(!filter.is_enabled(Level::Info, Some(4), Some(18)));
+    // coordinator records have no node, setup records no round
+    assert!(filter.is_enabled(Level::Info, Some(4), None));
+    assert!(filter.is_enabled(Level::Info, None, Some(17)));
+}
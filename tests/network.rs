@@ -0,0 +1,88 @@
+// checks of the tcp backend on the loopback interface: the coordinator and the node
+// processes run as threads of the test, talking over real sockets
+use distributed_mis::network::tcp::{Frame, run_coordinator, run_node_process, write_frame};
+use distributed_mis::roles::creater::{Creater};
+use distributed_mis::roles::desire::{DesireConfig};
+use distributed_mis::roles::node::{Algorithm, Message};
+use distributed_mis::roles::verifier::{Verifier};
+use rand::{SeedableRng};
+use rand::prng::XorShiftRng;
+use std::io::{ErrorKind};
+use std::net::{TcpListener, TcpStream};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+const NUM_PROCESS: usize = 2;
+
+// a port nobody listens on right now
+fn free_port() -> u16 {
+    TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port()
+}
+
+// connects once the coordinator listens
+fn connect(port: u16) -> TcpStream {
+    loop {
+        match TcpStream::connect(("127.0.0.1", port)) {
+            Ok(stream) => return stream,
+            Err(_) => thread::sleep(Duration::from_millis(20)),
+        }
+    }
+}
+
+fn spawn_nodes(graph: &[Vec<usize>], weights: &[f32], port: u16, algorithm: Algorithm) -> Vec<JoinHandle<()>> {
+    (0..NUM_PROCESS).map(|index| {
+        let (graph, weights) = (graph.to_vec(), weights.to_vec());
+        thread::spawn(move || {
+            run_node_process(&graph, &weights, index, NUM_PROCESS, &format!("127.0.0.1:{}", port), algorithm, DesireConfig::default())
+                .expect("node process fails");
+        })
+    }).collect()
+}
+
+#[test]
+fn loopback_run_finds_an_mis() {
+    let mut rng = XorShiftRng::seed_from_u64(1);
+    let mut creater = Creater::new();
+    let graph = creater.generate_with(40, 4, &mut rng);
+    for algorithm in [Algorithm::Luby, Algorithm::Metivier] {
+        let port = free_port();
+        let nodes = spawn_nodes(&graph, &creater.weights, port, algorithm);
+        let coordinator = run_coordinator(graph.len(), NUM_PROCESS, port).expect("coordinator fails");
+        let mis = coordinator.get_mis_result();
+        // the node processes leave once the coordinator closes their connections
+        drop(coordinator);
+        for node in nodes {
+            node.join().unwrap();
+        }
+        assert!(Verifier::new(&mis, &graph).verify(), "{:?}", algorithm);
+    }
+}
+
+// a process claiming a node the graph does not have
+#[test]
+fn hello_out_of_range() {
+    let port = free_port();
+    let client = thread::spawn(move || {
+        let mut stream = connect(port);
+        write_frame(&mut stream, &Frame::Hello(vec![0, 1, 7])).unwrap();
+    });
+    let error = run_coordinator(2, 1, port).err().expect("an id out of range is accepted");
+    assert_eq!(error.kind(), ErrorKind::InvalidData);
+    client.join().unwrap();
+}
+
+// a frame to a node the graph does not have ends the run instead of hanging it
+#[test]
+fn frame_out_of_range() {
+    let port = free_port();
+    let client = thread::spawn(move || {
+        let mut stream = connect(port);
+        write_frame(&mut stream, &Frame::Hello(vec![0, 1])).unwrap();
+        write_frame(&mut stream, &Frame::Peer((9, Message::Request((0, 0))))).unwrap();
+        // hold the connection until the coordinator closes it
+        let _ = distributed_mis::network::tcp::read_frame(&mut stream);
+    });
+    let error = run_coordinator(2, 1, port).err().expect("a frame out of range is relayed");
+    assert_eq!(error.kind(), ErrorKind::InvalidData);
+    client.join().unwrap();
+}
@@ -0,0 +1,595 @@
+// property checks of the solver on random graphs: every case is drawn from a seed, and a
+// failing graph is shrunk and written as an adjacency file that the -g option reads
+use distributed_mis::roles::baseline::{Baseline, GreedyOrder};
+use distributed_mis::roles::beeping::{BeepingChannel, BeepingSimulator, BeepState};
+use distributed_mis::roles::creater::{Creater};
+use distributed_mis::roles::deterministic::{DeterministicMis};
+use distributed_mis::roles::desire::{DesireConfig};
+use distributed_mis::roles::exact::{ExactSolver};
+use distributed_mis::roles::explorer::{Explorer};
+use distributed_mis::roles::node::{Algorithm, Node};
+use distributed_mis::roles::runner::{RunConfig, run_threaded};
+use distributed_mis::roles::scheduler::{Scheduler, Policy, Outcome};
+use distributed_mis::roles::shrinker::{Shrinker};
+use distributed_mis::roles::tracer::{Endpoint};
+use distributed_mis::roles::verifier::{ColoringVerifier, ColoringViolation, MatchingVerifier, MatchingViolation, Verifier, Violation};
+use rand::{Rng, SeedableRng};
+use rand::prng::XorShiftRng;
+use std::collections::{HashSet};
+use std::sync::mpsc::{channel};
+
+const NUM_CASE: u64 = 48;
+const MAX_NODE: usize = 48;
+const MAX_WEIGHT: usize = 100;
+// a few times the most rounds seen on these graph sizes, the algorithms take O(log n)
+const ROUND_BOUND: usize = 40;
+
+// the graphs Creater makes: bounded degree, with unit or random weights, and the same
+// with some nodes cut off from the start
+#[derive(Copy, Clone, Debug)]
+enum Model {
+    Uniform,
+    Weighted,
+    Isolated,
+}
+
+const MODELS: [Model; 3] = [Model::Uniform, Model::Weighted, Model::Isolated];
+
+impl Model {
+    fn name(&self) -> &'static str {
+        match self {
+            Model::Uniform => "uniform",
+            Model::Weighted => "weighted",
+            Model::Isolated => "isolated",
+        }
+    }
+
+    fn algorithms(&self) -> Vec<Algorithm> {
+        match self {
+            Model::Uniform => vec![Algorithm::Luby, Algorithm::Metivier],
+            Model::Weighted => vec![Algorithm::Weighted, Algorithm::Luby],
+            Model::Isolated => vec![Algorithm::Luby, Algorithm::Metivier, Algorithm::Weighted],
+        }
+    }
+
+    // enough nodes for every node to find its neighbors below the degree
+    fn generate(&self, seed: u64) -> (Vec<Vec<usize>>, Vec<f32>) {
+        let mut rng = XorShiftRng::seed_from_u64(seed);
+        let num_degree = rng.gen_range(2, 6);
+        let num_node = rng.gen_range(3 * num_degree, MAX_NODE + 1);
+        let mut creater = Creater::new();
+        let mut graph = creater.generate_with(num_node, num_degree, &mut rng);
+        if let Model::Weighted | Model::Isolated = self {
+            creater.random_weights_with(MAX_WEIGHT, &mut rng);
+        }
+        // degree 0 anywhere in the ids, the last node included now and then
+        if let Model::Isolated = self {
+            let num_isolated = rng.gen_range(1, 4);
+            for _ in 0..num_isolated {
+                let id = rng.gen_range(0, num_node);
+                graph[id].clear();
+                for dsts in graph.iter_mut() {
+                    dsts.retain(|n_id| *n_id != id);
+                }
+            }
+            if rng.gen() {
+                graph.push(vec![]);
+                creater.weights.push(1.0);
+            }
+        }
+        (graph, creater.weights)
+    }
+}
+
+// the mis and the rounds of a scheduled run, stopped at the round bound
+fn run_scheduled(graph: &[Vec<usize>], weights: &[f32], algorithm: Algorithm, seed: u64, desire: DesireConfig, policy: Policy) -> Result<(Vec<usize>, usize), String> {
+    let mut scheduler = Scheduler::new(graph, weights, algorithm, seed, policy.clone());
+    scheduler.set_desire(desire);
+    scheduler.coordinator.set_snapshot(ROUND_BOUND, true);
+    if scheduler.run() == Outcome::Deadlock {
+        return Err(format!("deadlock with the {} policy after {} deliveries", policy.name(), scheduler.num_delivery));
+    }
+    let undecided = scheduler.coordinator.residual.as_ref().map_or(0, |residual| residual.len());
+    if undecided > 0 {
+        return Err(format!("{} nodes undecided after {} rounds", undecided, ROUND_BOUND));
+    }
+    Ok((scheduler.coordinator.get_sorted_mis(), scheduler.coordinator.round))
+}
+
+// independence, maximality and termination on the scheduler, then the same mis and
+// rounds with other delivery orders and with threads
+fn check(graph: &[Vec<usize>], weights: &[f32], algorithm: Algorithm, seed: u64, desire: DesireConfig) -> Result<(), String> {
+    let (mis, round) = run_scheduled(graph, weights, algorithm, seed, desire, Policy::Fifo)?;
+    let violations = Verifier::new(&mis.iter().cloned().collect(), graph).find_violations();
+    if let Some(violation) = violations.first() {
+        return Err(format!("{:?} among {} violations", violation, violations.len()));
+    }
+    for policy in [Policy::Lifo, Policy::Random] {
+        let (other_mis, other_round) = run_scheduled(graph, weights, algorithm, seed, desire, policy.clone())?;
+        if (&other_mis, other_round) != (&mis, round) {
+            return Err(format!("fifo gives {:?} in {} rounds, {} gives {:?} in {}", mis, round, policy.name(), other_mis, other_round));
+        }
+    }
+    let mut config = RunConfig::new(algorithm, seed);
+    config.desire = desire;
+    config.snapshot = Some((ROUND_BOUND, true));
+    let threaded = run_threaded(graph, weights, &config);
+    let threaded_round = threaded.round();
+    let mut threaded_mis: Vec<usize> = threaded.get_mis_result().into_iter().collect();
+    threaded_mis.sort();
+    if (&threaded_mis, threaded_round) != (&mis, round) {
+        return Err(format!("scheduled runs give {:?} in {} rounds, threads give {:?} in {}", mis, round, threaded_mis, threaded_round));
+    }
+    Ok(())
+}
+
+fn check_model(model: Model) {
+    for seed in 0..NUM_CASE {
+        let (graph, weights) = model.generate(seed);
+        for algorithm in model.algorithms() {
+            let failure = match check(&graph, &weights, algorithm, seed, DesireConfig::default()) {
+                Ok(()) => continue,
+                Err(failure) => failure,
+            };
+            let mut shrinker = Shrinker::new(&graph, &weights);
+            shrinker.shrink(|graph, weights| check(graph, weights, algorithm, seed, DesireConfig::default()).is_err());
+            let path = format!("{}/{}-{}-{}.in", env!("CARGO_TARGET_TMPDIR"), model.name(), algorithm.name(), seed);
+            shrinker.store(&path).expect("unable to write the shrunk graph");
+            panic!("{} on the {} graph of seed {} with {} nodes: {}\nshrunk to {} nodes and {} edges: {}\nrerun with distributed_MIS -g {} -r 1 -s {} --algorithm {} --schedule fifo",
+                algorithm.name(), model.name(), seed, graph.len(), failure,
+                shrinker.num_node, shrinker.edges.len(), check(&shrinker.get_graph(), &shrinker.weights, algorithm, seed, DesireConfig::default()).unwrap_err(),
+                path, seed, algorithm.name());
+        }
+    }
+}
+
+#[test]
+fn uniform_graphs() {
+    check_model(Model::Uniform);
+}
+
+#[test]
+fn weighted_graphs() {
+    check_model(Model::Weighted);
+}
+
+#[test]
+fn graphs_with_isolated_nodes() {
+    check_model(Model::Isolated);
+}
+
+#[test]
+fn generation_follows_the_seed() {
+    for seed in 0..NUM_CASE {
+        assert_eq!(Model::Weighted.generate(seed), Model::Weighted.generate(seed));
+    }
+}
+
+// desire levels away from powers of 2 still give one mis and round count whatever the
+// delivery order
+#[test]
+fn tuned_desire_levels() {
+    let mut desire = DesireConfig::default();
+    desire.apply("up=1.5,down=3,threshold=1,floor=0.01").unwrap();
+    for seed in 0..NUM_CASE {
+        let (graph, weights) = Model::Weighted.generate(seed);
+        for algorithm in Model::Weighted.algorithms() {
+            check(&graph, &weights, algorithm, seed, desire).unwrap_or_else(|failure| panic!("{} on seed {}: {}", algorithm.name(), seed, failure));
+        }
+    }
+}
+
+// a neighbor is assumed at the configured initial level until it says otherwise,
+// whether it is registered before or after the desire is set
+#[test]
+fn neighbors_start_at_the_initial_desire_level() {
+    let mut desire = DesireConfig::default();
+    desire.apply("initial=0.25").unwrap();
+    let (central_sender, _central_receiver) = channel();
+    let (mut node, _) = Node::new(0, central_sender);
+    let (sender, _receiver) = channel();
+    node.register_neighbor(1, sender.clone());
+    node.set_desire(desire);
+    node.register_neighbor(2, sender);
+    assert_eq!(node.desire_level, 0.25);
+    for id in 1..=2 {
+        assert_eq!(node.neighbors[&id].desire_level, 0.25, "neighbor {}", id);
+    }
+}
+
+// a greedy mis passes, and it breaks once a neighbor joins or a member leaves
+#[test]
+fn verifier_flags_broken_mis() {
+    for seed in 0..NUM_CASE {
+        let (graph, _) = Model::Uniform.generate(seed);
+        let baseline = Baseline::new(&graph);
+        let mis = baseline.greedy(GreedyOrder::Random(seed));
+        assert!(Verifier::new(&mis, &graph).verify());
+        let mut ids: Vec<usize> = mis.iter().cloned().collect();
+        ids.sort();
+        let member = ids[seed as usize % ids.len()];
+        let neighbor = *baseline.neighbors[member].iter().min().unwrap();
+
+        let mut joined: HashSet<usize> = mis.clone();
+        joined.insert(neighbor);
+        let violations = Verifier::new(&joined, &graph).find_violations();
+        assert!(violations.contains(&Violation::Independence(member.min(neighbor), member.max(neighbor))));
+
+        let mut left: HashSet<usize> = mis.clone();
+        left.remove(&member);
+        let violations = Verifier::new(&left, &graph).find_violations();
+        assert!(violations.contains(&Violation::Maximality(member)));
+    }
+}
+
+// a graph failing as long as it holds a triangle shrinks to the triangle alone
+#[test]
+fn shrinking_keeps_the_failure() {
+    let has_triangle = |graph: &[Vec<usize>], _weights: &[f32]| {
+        let edges = Shrinker::new(graph, &vec![1.0; graph.len()]).edges;
+        edges.iter().any(|(u, v)| (0..graph.len()).any(|w| edges.contains(&(*u.min(&w), *u.max(&w))) && edges.contains(&(*v.min(&w), *v.max(&w)))))
+    };
+    for seed in 0..NUM_CASE {
+        let (graph, weights) = Model::Weighted.generate(seed);
+        if !has_triangle(&graph, &weights) {
+            continue;
+        }
+        let mut shrinker = Shrinker::new(&graph, &weights);
+        shrinker.shrink(has_triangle);
+        assert_eq!((shrinker.num_node, shrinker.edges.len()), (3, 3));
+        assert_eq!(shrinker.weights, vec![1.0; 3]);
+    }
+}
+
+// a run stopped after one round only leaves its undecided nodes uncovered, and the
+// greedy completion makes it an mis
+#[test]
+fn completion_of_stopped_runs() {
+    for seed in 0..NUM_CASE {
+        let (graph, weights) = Model::Uniform.generate(seed);
+        let mut scheduler = Scheduler::new(&graph, &weights, Algorithm::Luby, seed, Policy::Fifo);
+        scheduler.coordinator.set_snapshot(1, true);
+        assert_eq!(scheduler.run(), Outcome::Finished);
+        let mis = scheduler.coordinator.get_mis_result();
+        let undecided = scheduler.coordinator.residual.clone().unwrap_or_default();
+        for violation in Verifier::new(&mis, &graph).find_violations() {
+            assert!(matches!(violation, Violation::Maximality(id) if undecided.contains(&id)), "{:?}", violation);
+        }
+        let mut completed = mis.clone();
+        completed.extend(Baseline::new(&graph).complete(&mis, &undecided));
+        assert!(Verifier::new(&completed, &graph).verify());
+    }
+}
+
+fn load_example(name: &str) -> (Vec<Vec<usize>>, Vec<f32>) {
+    let mut creater = Creater::new();
+    let graph = creater.load_graph(&format!("{}/{}", env!("CARGO_MANIFEST_DIR"), name)).expect("unable to read the example graph");
+    (graph, creater.weights)
+}
+
+#[test]
+fn held_back_policies_parse() {
+    assert_eq!(Policy::parse("starve:1"), Ok(Policy::Starve(1)));
+    assert_eq!(Policy::parse("starve:1").unwrap().name(), "starve:1");
+    let delayed = Policy::parse("delay:2-1, 0-1").unwrap();
+    assert_eq!(delayed, Policy::DelayEdges(vec![(0, 1), (1, 2)].into_iter().collect()));
+    assert_eq!(delayed.name(), "delay:0-1,1-2");
+    for policy in ["starve", "starve:x", "delay:1", "delay:1-2-3", "delay:1-x", "fifo:1"] {
+        assert!(Policy::parse(policy).is_err(), "{}", policy);
+    }
+}
+
+// holding back the messages to the middle node of the path, or on one of its edges, only
+// changes the order of the deliveries
+#[test]
+fn starved_node_on_a_path() {
+    let (graph, weights) = load_example("d1.in");
+    for seed in 0..NUM_CASE {
+        let expected = run_scheduled(&graph, &weights, Algorithm::Luby, seed, DesireConfig::default(), Policy::Fifo).unwrap();
+        assert_eq!(run_scheduled(&graph, &weights, Algorithm::Luby, seed, DesireConfig::default(), Policy::Starve(1)), Ok(expected));
+    }
+}
+
+#[test]
+fn delayed_edge_on_a_path() {
+    let (graph, weights) = load_example("d1.in");
+    for seed in 0..NUM_CASE {
+        let expected = run_scheduled(&graph, &weights, Algorithm::Luby, seed, DesireConfig::default(), Policy::Fifo).unwrap();
+        let delayed = Policy::parse("delay:0-1").unwrap();
+        assert_eq!(run_scheduled(&graph, &weights, Algorithm::Luby, seed, DesireConfig::default(), delayed), Ok(expected));
+    }
+}
+
+// a node without neighbors joins in the first round, whatever the order
+#[test]
+fn isolated_node_finishes() {
+    let graph = vec![vec![1], vec![0], vec![]];
+    for algorithm in Model::Isolated.algorithms() {
+        for policy in [Policy::Fifo, Policy::Lifo, Policy::Starve(2)] {
+            let (mis, round) = run_scheduled(&graph, &[1.0; 3], algorithm, 0, DesireConfig::default(), policy).unwrap();
+            assert!(mis.contains(&2));
+            assert_eq!(mis.len(), 2);
+            assert_eq!(round, 0);
+        }
+    }
+}
+
+// without its Start, the middle node never answers its neighbors nor reports to the
+// coordinator, which waits with nothing left to deliver
+#[test]
+fn dropped_start_deadlocks() {
+    let (graph, weights) = load_example("d1.in");
+    let mut scheduler = Scheduler::new(&graph, &weights, Algorithm::Luby, 0, Policy::Fifo);
+    scheduler.settle();
+    scheduler.in_flight.retain(|in_flight| in_flight.to != Endpoint::Node(1));
+    assert!(!scheduler.in_flight.is_empty());
+    assert_eq!(scheduler.run(), Outcome::Deadlock);
+    assert!(!scheduler.coordinator.is_finished());
+}
+
+// the shipped examples are covered well within the default budget of the explore command
+#[test]
+fn examples_are_explored_completely() {
+    for name in ["d1.in", "d2.in"] {
+        let (graph, weights) = load_example(name);
+        for algorithm in [Algorithm::Luby, Algorithm::Weighted] {
+            let exploration = Explorer::new(&graph, &weights, algorithm, 1).explore(100000);
+            assert!(exploration.is_complete, "{} {:?}", name, algorithm);
+            assert_eq!(exploration.num_deadlock + exploration.num_panic + exploration.num_wrong + exploration.num_step_limit, 0);
+            assert_eq!(exploration.results.len(), 1);
+        }
+    }
+}
+
+// a search cut short still returns an independent set, no larger than the maximum one
+#[test]
+fn exact_search_within_a_budget() {
+    for seed in 0..NUM_CASE {
+        let (graph, _) = Model::Uniform.generate(seed);
+        let maximum = ExactSolver::new(&graph).solve();
+        let mut solver = ExactSolver::new(&graph);
+        solver.set_max_branch(1);
+        let bounded = solver.solve();
+        let violations = Verifier::new(&bounded, &graph).find_violations();
+        assert!(violations.iter().all(|violation| !matches!(violation, Violation::Independence(..))));
+        assert!(bounded.len() <= maximum.len());
+        assert!(solver.is_exhausted || bounded.len() == maximum.len());
+    }
+}
+
+// a few dense parts side by side, with twins of some nodes, adjacent or not, and a few
+// nodes without neighbors, up to 20 nodes
+fn small_graph(seed: u64) -> Vec<Vec<usize>> {
+    let mut rng = XorShiftRng::seed_from_u64(seed);
+    let mut graph: Vec<Vec<usize>> = vec![];
+    while graph.len() < 12 {
+        let start = graph.len();
+        let num_node = rng.gen_range(2, 16 - start);
+        let density = rng.gen_range(0.2, 0.6);
+        graph.extend((0..num_node).map(|_| vec![]));
+        for i in start..graph.len() {
+            for j in (i + 1)..graph.len() {
+                // a cycle through the part leaves no node dominated
+                if j == i + 1 || (i == start && j + 1 == graph.len()) || rng.gen_bool(density) {
+                    graph[i].push(j);
+                    graph[j].push(i);
+                }
+            }
+        }
+    }
+    for _ in 0..rng.gen_range(0, 4) {
+        let id = rng.gen_range(0, graph.len());
+        let twin = graph.len();
+        let dsts = graph[id].clone();
+        graph.push(dsts.clone());
+        for n_id in dsts {
+            graph[n_id].push(twin);
+        }
+        if rng.gen() {
+            graph[id].push(twin);
+            graph[twin].push(id);
+        }
+    }
+    for _ in 0..rng.gen_range(0, 3) {
+        graph.push(vec![]);
+    }
+    graph
+}
+
+// size of a maximum independent set of the nodes in remaining, over every choice of the
+// lowest one
+fn brute_force_maximum(adjacency: &[u32], remaining: u32) -> usize {
+    if remaining == 0 {
+        return 0;
+    }
+    let id = remaining.trailing_zeros() as usize;
+    let without = brute_force_maximum(adjacency, remaining & !(1 << id));
+    let with = 1 + brute_force_maximum(adjacency, remaining & !(1 << id) & !adjacency[id]);
+    without.max(with)
+}
+
+// the small graphs are cheap, many of them reach the branching
+#[test]
+fn exact_search_finds_a_maximum() {
+    for seed in 0..20 * NUM_CASE {
+        let graph = small_graph(seed);
+        assert!(graph.len() <= 20);
+        let adjacency: Vec<u32> = graph.iter().map(|dsts| dsts.iter().fold(0, |bits, n_id| bits | 1 << n_id)).collect();
+        let maximum = ExactSolver::new(&graph).solve();
+        let violations = Verifier::new(&maximum, &graph).find_violations();
+        assert!(violations.iter().all(|violation| !matches!(violation, Violation::Independence(..))), "{:?}", graph);
+        assert_eq!(maximum.len(), brute_force_maximum(&adjacency, (1 << graph.len()) - 1), "{:?}", graph);
+    }
+}
+
+// the weighted runs are independent sets, heavier than the unweighted ones on the same
+// graphs and seeds, and not far from the greedy weighted baseline in total
+#[test]
+fn weighted_runs_against_greedy() {
+    let (mut weight, mut unweighted, mut greedy) = (0.0, 0.0, 0.0);
+    for seed in 0..NUM_CASE {
+        let (graph, weights) = Model::Weighted.generate(seed);
+        let total = |mis: &HashSet<usize>| mis.iter().map(|id| weights[*id]).sum::<f32>();
+        let mis = run_threaded(&graph, &weights, &RunConfig::new(Algorithm::Weighted, seed)).get_mis_result();
+        assert!(Verifier::new(&mis, &graph).verify(), "seed {}", seed);
+        weight += total(&mis);
+        unweighted += total(&run_threaded(&graph, &weights, &RunConfig::new(Algorithm::Luby, seed)).get_mis_result());
+        let baseline = Baseline::new(&graph).weighted_greedy(&weights);
+        assert!(Verifier::new(&baseline, &graph).verify(), "seed {}", seed);
+        greedy += total(&baseline);
+    }
+    assert!(weight > unweighted);
+    assert!(weight > 0.8 * greedy);
+}
+
+#[test]
+fn malformed_graph_files() {
+    let path = std::env::temp_dir().join(format!("distributed_mis_{}_graph", std::process::id())).to_string_lossy().to_string();
+    for (text, reason) in [("0:2 1\n1:x 0\n", "line 2: invalid node weight x"), ("0 1\n1 0 y\n", "line 2: invalid node id y"), ("0 3\n1\n", "line 1: node id out of range 3")] {
+        std::fs::write(&path, text).unwrap();
+        let error = Creater::new().load_graph(&path).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+        assert_eq!(error.to_string(), reason);
+    }
+    std::fs::write(&path, "0:2.5 1\n1 0\n").unwrap();
+    let mut creater = Creater::new();
+    assert_eq!(creater.load_graph(&path).unwrap(), vec![vec![1], vec![0]]);
+    assert_eq!(creater.weights, vec![2.5, 1.0]);
+    std::fs::remove_file(&path).unwrap();
+}
+
+// a beep reaches the active neighbors of an active beeping node, and the node itself
+// only hears the others
+#[test]
+fn beeping_channel_slots() {
+    // the path 0 - 1 - 2 - 3
+    let mut channel = BeepingChannel::new(&[vec![1], vec![2], vec![3], vec![]]);
+    assert_eq!(channel.slot(&[true, false, true, true], &[true, true, true, false]), vec![false, true, false, false]);
+    assert_eq!(channel.slot(&[true, true, false, false], &[true, true, true, true]), vec![true, true, true, false]);
+    assert_eq!((channel.num_slot, channel.num_beep), (2, 4));
+}
+
+#[test]
+fn beeping_on_every_model() {
+    for model in MODELS.iter() {
+        for seed in 0..NUM_CASE {
+            let (graph, _) = model.generate(seed);
+            let mut simulator = BeepingSimulator::new(&graph, seed);
+            simulator.run();
+            let mis = simulator.get_mis_result();
+            let violations = Verifier::new(&mis, &graph).find_violations();
+            assert!(violations.is_empty(), "{} seed {} {:?}", model.name(), seed, violations);
+            assert!(simulator.decide_round.iter().all(|round| round.is_some()), "{} seed {}", model.name(), seed);
+            assert_eq!(simulator.channel.num_slot, 2 * simulator.phase);
+
+            // a covered node that joins anyway, and a member that leaves
+            let member = *mis.iter().min().unwrap();
+            let covered = simulator.channel.neighbors[member].first().cloned();
+            if let Some(covered) = covered {
+                simulator.nodes[covered].state = BeepState::InMis;
+                let violations = Verifier::new(&simulator.get_mis_result(), &graph).find_violations();
+                assert!(violations.contains(&Violation::Independence(member.min(covered), member.max(covered))));
+                simulator.nodes[covered].state = BeepState::Covered;
+            }
+            simulator.nodes[member].state = BeepState::Covered;
+            let violations = Verifier::new(&simulator.get_mis_result(), &graph).find_violations();
+            assert!(violations.contains(&Violation::Maximality(member)), "{} seed {}", model.name(), seed);
+        }
+    }
+}
+
+// the reduced colors are a (max degree + 1)-coloring, and the color classes an mis
+#[test]
+fn deterministic_on_every_model() {
+    for model in MODELS.iter() {
+        for seed in 0..NUM_CASE {
+            let (graph, _) = model.generate(seed);
+            let mut solver = DeterministicMis::new(&graph);
+            solver.reduce_colors();
+            let colors: Vec<Option<usize>> = solver.colors.iter().map(|color| Some(*color)).collect();
+            let violations = ColoringVerifier::new(&colors, &graph).find_violations();
+            assert!(violations.is_empty(), "{} seed {} {:?}", model.name(), seed, violations);
+            assert!(solver.num_color <= solver.palette);
+            solver.select();
+            let mis = solver.get_mis_result();
+            let violations = Verifier::new(&mis, &graph).find_violations();
+            assert!(violations.is_empty(), "{} seed {} {:?}", model.name(), seed, violations);
+            assert!(solver.decide_round.iter().all(|round| round.is_some()), "{} seed {}", model.name(), seed);
+            assert!(solver.mis_round <= solver.palette);
+
+            // a neighbor that joins anyway, and a member that leaves
+            let member = *mis.iter().min().unwrap();
+            if let Some(neighbor) = solver.neighbors[member].first().cloned() {
+                solver.is_in_mis[neighbor] = true;
+                let violations = Verifier::new(&solver.get_mis_result(), &graph).find_violations();
+                assert!(violations.contains(&Violation::Independence(member.min(neighbor), member.max(neighbor))));
+                solver.is_in_mis[neighbor] = false;
+            }
+            solver.is_in_mis[member] = false;
+            let violations = Verifier::new(&solver.get_mis_result(), &graph).find_violations();
+            assert!(violations.contains(&Violation::Maximality(member)), "{} seed {}", model.name(), seed);
+        }
+    }
+}
+
+#[test]
+fn coloring_on_every_model() {
+    for model in MODELS.iter() {
+        for seed in 0..NUM_CASE {
+            let (graph, weights) = model.generate(seed);
+            let outcome = run_threaded(&graph, &weights, &RunConfig::new(Algorithm::Coloring, seed));
+            let verifier = ColoringVerifier::new(&outcome.coordinator.colors, &graph);
+            let violations = verifier.find_violations();
+            assert!(violations.is_empty(), "{} seed {} {:?}", model.name(), seed, violations);
+            assert!(verifier.num_color() <= verifier.max_degree() + 1);
+        }
+    }
+}
+
+// a triangle 0 1 2 with 3 hanging off 2, so the palette is 0 to 3
+#[test]
+fn coloring_verifier_flags_broken_colorings() {
+    let graph = vec![vec![1, 2], vec![2], vec![3], vec![]];
+    assert!(ColoringVerifier::new(&[Some(0), Some(1), Some(2), Some(0)], &graph).verify());
+    let verifier = ColoringVerifier::new(&[Some(3), Some(1), Some(2), Some(3)], &graph);
+    assert_eq!((verifier.verify(), verifier.num_color(), verifier.max_degree()), (true, 3, 3));
+    let violations = ColoringVerifier::new(&[Some(0), Some(0), None, Some(4)], &graph).find_violations();
+    assert_eq!(violations, vec![ColoringViolation::Uncolored(2), ColoringViolation::Palette(3, 4), ColoringViolation::Conflict(0, 1)]);
+    // an uncolored pair is not a conflict
+    let violations = ColoringVerifier::new(&[Some(0), Some(1), None, None], &graph).find_violations();
+    assert_eq!(violations, vec![ColoringViolation::Uncolored(2), ColoringViolation::Uncolored(3)]);
+}
+
+#[test]
+fn matching_on_every_model() {
+    for model in MODELS.iter() {
+        for seed in 0..NUM_CASE {
+            let (graph, weights) = model.generate(seed);
+            let outcome = run_threaded(&graph, &weights, &RunConfig::new(Algorithm::Matching, seed));
+            let verifier = MatchingVerifier::new(&outcome.coordinator.partners, &graph);
+            let violations = verifier.find_violations();
+            assert!(violations.is_empty(), "{} seed {} {:?}", model.name(), seed, violations);
+            assert!(!verifier.get_matching().is_empty(), "{} seed {}", model.name(), seed);
+        }
+    }
+}
+
+// the path 0 - 1 - 2 - 3
+#[test]
+fn matching_verifier_flags_broken_matchings() {
+    let graph = vec![vec![1], vec![2], vec![3], vec![]];
+    let verifier = MatchingVerifier::new(&[Some(1), Some(0), Some(3), Some(2)], &graph);
+    assert!(verifier.verify());
+    assert_eq!(verifier.get_matching(), vec![(0, 1), (2, 3)]);
+    assert!(MatchingVerifier::new(&[None, Some(2), Some(1), None], &graph).verify());
+    let cases = [
+        ([Some(2), None, Some(0), None], vec![MatchingViolation::NotEdge(0, 2)]),
+        ([Some(1), Some(2), Some(1), None], vec![MatchingViolation::Asymmetric(0)]),
+        ([Some(9), None, Some(3), Some(2)], vec![MatchingViolation::Asymmetric(0)]),
+        ([None, None, Some(3), Some(2)], vec![MatchingViolation::Maximality(0, 1)]),
+        ([None, None, None, None], vec![MatchingViolation::Maximality(0, 1), MatchingViolation::Maximality(1, 2), MatchingViolation::Maximality(2, 3)]),
+    ];
+    for (partners, violations) in cases.iter() {
+        assert_eq!(MatchingVerifier::new(partners, &graph).find_violations(), *violations, "{:?}", partners);
+    }
+}
@@ -0,0 +1,71 @@
+// checks of what lets a run be taken up again: checkpoints resume it where it stopped
+use distributed_mis::roles::checkpoint::{Checkpoint};
+use distributed_mis::roles::creater::{Creater};
+use distributed_mis::roles::node::{Algorithm};
+use distributed_mis::roles::runner::{RunConfig, run_threaded};
+use rand::{SeedableRng};
+use rand::prng::XorShiftRng;
+
+const NUM_CASE: u64 = 8;
+
+fn temp_path(name: &str, seed: u64) -> String {
+    std::env::temp_dir().join(format!("distributed_mis_{}_{}_{}", std::process::id(), name, seed)).to_string_lossy().to_string()
+}
+
+// a run stopped in the round after its checkpoint of round 2 and resumed from that
+// checkpoint ends like the uninterrupted run with the same seed
+#[test]
+fn resumed_run_matches_the_uninterrupted_one() {
+    let mut num_resumed = 0;
+    for seed in 0..NUM_CASE {
+        let mut rng = XorShiftRng::seed_from_u64(seed);
+        let mut creater = Creater::new();
+        let graph = creater.generate_with(300, 6, &mut rng);
+        let algorithm = [Algorithm::Luby, Algorithm::Weighted][seed as usize % 2];
+        if algorithm == Algorithm::Weighted {
+            creater.random_weights_with(100, &mut rng);
+        }
+        let weights = creater.weights.clone();
+        let uninterrupted = run_threaded(&graph, &weights, &RunConfig::new(algorithm, seed));
+        if uninterrupted.round() <= 3 {
+            continue;
+        }
+
+        let path = temp_path("checkpoint", seed);
+        let mut config = RunConfig::new(algorithm, seed);
+        config.checkpoint = Some((path.clone(), 2));
+        config.snapshot = Some((3, true));
+        let stopped = run_threaded(&graph, &weights, &config);
+        assert_eq!(stopped.round(), 3);
+        assert!(!stopped.undecided().is_empty());
+        let checkpoint = Checkpoint::load(&path).expect("no checkpoint written");
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(checkpoint.round, 2);
+        assert!(!checkpoint.nodes.is_empty());
+
+        let mut config = RunConfig::new(checkpoint.algorithm, checkpoint.seed);
+        config.desire = checkpoint.desire;
+        let (graph, weights) = (checkpoint.graph.clone(), checkpoint.weights.clone());
+        config.resume = Some(checkpoint);
+        let resumed = run_threaded(&graph, &weights, &config);
+        assert_eq!(resumed.get_mis_result(), uninterrupted.get_mis_result(), "seed {}", seed);
+        assert_eq!(resumed.round(), uninterrupted.round(), "seed {}", seed);
+        num_resumed += 1;
+    }
+    assert!(num_resumed > 0);
+}
+
+// checkpoints that cannot be written are counted, the run itself goes on undisturbed
+#[test]
+fn failed_checkpoints_keep_the_run() {
+    let mut rng = XorShiftRng::seed_from_u64(0);
+    let graph = Creater::new().generate_with(200, 6, &mut rng);
+    let weights = vec![1.0; graph.len()];
+    let uninterrupted = run_threaded(&graph, &weights, &RunConfig::new(Algorithm::Luby, 0));
+    let mut config = RunConfig::new(Algorithm::Luby, 0);
+    config.checkpoint = Some((temp_path("missing_dir", 0) + "/checkpoint", 1));
+    let outcome = run_threaded(&graph, &weights, &config);
+    assert_eq!(outcome.coordinator.num_checkpoint, 0);
+    assert_eq!(outcome.coordinator.num_failed_checkpoint, uninterrupted.round());
+    assert_eq!(outcome.get_mis_result(), uninterrupted.get_mis_result());
+}
@@ -0,0 +1,99 @@
+// checks of the lockstep ruling set simulator: the sets it returns keep the rulers
+// alpha apart and every node within beta of a ruler, as checked by the verifier
+use distributed_mis::roles::creater::{Creater};
+use distributed_mis::roles::ruling::{RulingSimulator};
+use distributed_mis::roles::verifier::{RulingVerifier, RulingViolation, Verifier};
+use rand::{Rng, SeedableRng};
+use rand::prng::XorShiftRng;
+use std::collections::{HashSet};
+
+const NUM_CASE: u64 = 12;
+
+fn random_graph(seed: u64) -> Vec<Vec<usize>> {
+    let mut rng = XorShiftRng::seed_from_u64(seed);
+    let num_node = rng.gen_range(10, 60);
+    let degree = rng.gen_range(2, 6);
+    Creater::new().generate_with(num_node, degree, &mut rng)
+}
+
+// number of bits of the largest node id, as in the bit splitting
+fn id_bits(num_node: usize) -> usize {
+    (usize::BITS - num_node.saturating_sub(1).leading_zeros()) as usize
+}
+
+// 0 - 1 - 2 - 3 - 4, and 5 on its own
+fn path_graph() -> Vec<Vec<usize>> {
+    vec![vec![1], vec![2], vec![3], vec![4], vec![], vec![]]
+}
+
+fn rulers(ids: &[usize]) -> HashSet<usize> {
+    ids.iter().cloned().collect()
+}
+
+#[test]
+fn distance_mis_is_a_ruling_set() {
+    for seed in 0..NUM_CASE {
+        let graph = random_graph(seed);
+        for k in 1..=3 {
+            let mut simulator = RulingSimulator::new(&graph, seed);
+            simulator.distance_mis(k);
+            let rulers = simulator.get_rulers();
+            let violations = RulingVerifier::new(&rulers, &graph).find_violations(k + 1, k);
+            assert!(violations.is_empty(), "seed {} k {} {:?}", seed, k, violations);
+            if k == 1 {
+                assert!(Verifier::new(&rulers, &graph).verify(), "seed {}", seed);
+            }
+        }
+    }
+}
+
+#[test]
+fn bit_ruling_set_is_a_ruling_set() {
+    for seed in 0..NUM_CASE {
+        let graph = random_graph(seed);
+        for alpha in 2..=4 {
+            let mut simulator = RulingSimulator::new(&graph, seed);
+            simulator.bit_ruling_set(alpha);
+            let beta = (alpha - 1) * id_bits(graph.len());
+            let violations = RulingVerifier::new(&simulator.get_rulers(), &graph).find_violations(alpha, beta);
+            assert!(violations.is_empty(), "seed {} alpha {} {:?}", seed, alpha, violations);
+        }
+    }
+}
+
+// a beta large enough for the bit splitting gets its guarantee, a smaller one the
+// distance mis, and the guaranteed beta holds either way
+#[test]
+fn ruling_set_keeps_its_guarantee() {
+    for seed in 0..NUM_CASE {
+        let graph = random_graph(seed);
+        for (alpha, beta) in [(2, 1), (3, 2), (3, 4), (2, 64), (3, 64)] {
+            let mut simulator = RulingSimulator::new(&graph, seed);
+            let guaranteed = simulator.ruling_set(alpha, beta).unwrap();
+            assert!(guaranteed <= beta);
+            let bit_beta = (alpha - 1) * id_bits(graph.len());
+            assert_eq!(guaranteed, if beta >= bit_beta { bit_beta } else { alpha - 1 });
+            let violations = RulingVerifier::new(&simulator.get_rulers(), &graph).find_violations(alpha, guaranteed);
+            assert!(violations.is_empty(), "seed {} ({}, {}) {:?}", seed, alpha, beta, violations);
+        }
+    }
+    let mut simulator = RulingSimulator::new(&path_graph(), 0);
+    assert!(simulator.ruling_set(1, 3).is_err());
+    assert!(simulator.ruling_set(4, 2).is_err());
+    assert!(simulator.get_rulers().is_empty());
+}
+
+#[test]
+fn verifier_rejects_bad_sets() {
+    let graph = path_graph();
+    let verifier = RulingVerifier::new(&rulers(&[0, 3, 5]), &graph);
+    assert!(verifier.verify(2, 1));
+    assert_eq!(verifier.find_violations(4, 1), vec![RulingViolation::TooClose(0, 3, 3)]);
+    assert_eq!(RulingVerifier::new(&rulers(&[0, 2, 5]), &graph).find_violations(3, 2),
+        vec![RulingViolation::TooClose(0, 2, 2)]);
+    assert_eq!(RulingVerifier::new(&rulers(&[0, 5]), &graph).find_violations(2, 3),
+        vec![RulingViolation::TooFar(4, Some(4))]);
+    // nothing reaches the isolated node
+    assert_eq!(RulingVerifier::new(&rulers(&[2]), &graph).find_violations(2, 2),
+        vec![RulingViolation::TooFar(5, None)]);
+}
@@ -0,0 +1,85 @@
+// checks of the residual graph left by a stopped run: its components split the
+// undecided nodes, and the deterministic mis of the residual completes the partial one
+use distributed_mis::roles::creater::{Creater};
+use distributed_mis::roles::graph::{undirected_neighbors};
+use distributed_mis::roles::node::{Algorithm};
+use distributed_mis::roles::runner::{RunConfig, run_threaded};
+use distributed_mis::roles::shattering::{ResidualGraph};
+use distributed_mis::roles::verifier::{Verifier};
+use rand::{SeedableRng};
+use rand::prng::XorShiftRng;
+use std::collections::{BTreeMap, HashSet};
+
+const NUM_CASE: u64 = 12;
+
+#[test]
+fn components_of_a_small_residual() {
+    // the path 0 - 1 - 2 - 3 - 4 - 5 and the edge 6 - 7
+    let graph = vec![vec![1], vec![2], vec![3], vec![4], vec![5], vec![], vec![7], vec![]];
+    let residual_graph = ResidualGraph::new(&graph, &[5, 0, 1, 3, 7, 4]);
+    assert_eq!(residual_graph.nodes, vec![0, 1, 3, 4, 5, 7]);
+    assert_eq!(residual_graph.neighbors, vec![vec![1], vec![0], vec![3], vec![2, 4], vec![3], vec![]]);
+    assert_eq!(residual_graph.components, vec![vec![3, 4, 5], vec![0, 1], vec![7]]);
+    assert_eq!(residual_graph.largest_component(), 3);
+    let distribution: BTreeMap<usize, usize> = [(1, 1), (2, 1), (3, 1)].iter().cloned().collect();
+    assert_eq!(residual_graph.size_distribution(), distribution);
+    // an mis of the residual graph itself, 7 has no neighbor left
+    let (finished, _) = residual_graph.finish_deterministic();
+    assert!(finished.contains(&7));
+    let indices: HashSet<usize> = (0..residual_graph.nodes.len()).filter(|i| finished.contains(&residual_graph.nodes[*i])).collect();
+    assert_eq!(indices.len(), finished.len());
+    assert!(Verifier::new(&indices, &residual_graph.neighbors).verify());
+
+    let empty = ResidualGraph::new(&graph, &[]);
+    assert!(empty.components.is_empty());
+    assert_eq!(empty.largest_component(), 0);
+    assert_eq!(empty.finish_deterministic(), (HashSet::new(), 0));
+}
+
+// runs stopped after their second round, as with --shatter 2 --shatter-finish
+#[test]
+fn finished_residual_completes_the_mis() {
+    let mut num_residual = 0;
+    for seed in 0..NUM_CASE {
+        let mut rng = XorShiftRng::seed_from_u64(seed);
+        let mut creater = Creater::new();
+        let graph = creater.generate_with(300, 10, &mut rng);
+        let algorithm = [Algorithm::Luby, Algorithm::Weighted, Algorithm::Metivier][seed as usize % 3];
+        if algorithm == Algorithm::Weighted {
+            creater.random_weights_with(100, &mut rng);
+        }
+        let mut config = RunConfig::new(algorithm, seed);
+        config.snapshot = Some((2, true));
+        let outcome = run_threaded(&graph, &creater.weights, &config);
+        let residual = outcome.coordinator.residual.clone().unwrap_or_default();
+        let residual_graph = ResidualGraph::new(&graph, &residual);
+
+        // the components split the residual nodes and no edge joins two of them
+        let mut component_of: BTreeMap<usize, usize> = BTreeMap::new();
+        for (index, component) in residual_graph.components.iter().enumerate() {
+            assert!(!component.is_empty());
+            for id in component.iter() {
+                assert!(component_of.insert(*id, index).is_none(), "seed {} node {} twice", seed, id);
+            }
+        }
+        assert_eq!(component_of.keys().cloned().collect::<HashSet<usize>>(), residual.iter().cloned().collect(), "seed {}", seed);
+        let neighbors = undirected_neighbors(&graph);
+        for id in residual.iter() {
+            for n_id in neighbors[*id].iter().filter(|n_id| component_of.contains_key(n_id)) {
+                assert_eq!(component_of[id], component_of[n_id], "seed {} edge {}-{}", seed, id, n_id);
+            }
+        }
+        let sizes: Vec<usize> = residual_graph.components.iter().map(|c| c.len()).collect();
+        assert!(sizes.windows(2).all(|pair| pair[0] >= pair[1]), "seed {} {:?}", seed, sizes);
+
+        let mut mis = outcome.get_mis_result();
+        assert!(mis.iter().all(|id| !component_of.contains_key(id)));
+        let (finished, _) = residual_graph.finish_deterministic();
+        assert!(finished.iter().all(|id| component_of.contains_key(id)));
+        mis.extend(finished);
+        let violations = Verifier::new(&mis, &graph).find_violations();
+        assert!(violations.is_empty(), "seed {} {:?}", seed, violations);
+        num_residual += residual.len();
+    }
+    assert!(num_residual > 0);
+}
@@ -0,0 +1,95 @@
+// checks of the self-stabilizing mis: from any corrupted state it converges to a valid
+// mis, and once there the rules leave the mis alone
+use distributed_mis::roles::creater::{Creater};
+use distributed_mis::roles::stabilizing::{StabilizingSimulator, StabilizingState};
+use distributed_mis::roles::verifier::{Verifier};
+use rand::{Rng, SeedableRng};
+use rand::prng::XorShiftRng;
+
+const NUM_CASE: u64 = 16;
+const MAX_ROUND: usize = 10000;
+const NUM_FAULT: usize = 5;
+
+fn random_simulator(seed: u64) -> StabilizingSimulator {
+    let mut rng = XorShiftRng::seed_from_u64(seed);
+    let num_node = rng.gen_range(20, 200);
+    let num_degree = rng.gen_range(2, 12);
+    let graph = Creater::new().generate_with(num_node, num_degree, &mut rng);
+    StabilizingSimulator::new(&graph, seed)
+}
+
+fn graph_of(simulator: &StabilizingSimulator) -> Vec<Vec<usize>> {
+    simulator.neighbors.clone()
+}
+
+// a stable simulator keeps its mis, and no node joins or leaves, for this many rounds
+fn assert_unchanged(simulator: &mut StabilizingSimulator, num_round: usize, seed: u64) {
+    let mis = simulator.get_mis_result();
+    let first = simulator.history.len();
+    for _ in 0..num_round {
+        simulator.step();
+        assert!(simulator.is_stable(), "seed {}", seed);
+    }
+    assert_eq!(simulator.get_mis_result(), mis, "seed {}", seed);
+    assert!(simulator.history[first..].iter().all(|state| state.joined.is_empty() && state.removed.is_empty()), "seed {}", seed);
+}
+
+#[test]
+fn fully_corrupted_state_converges() {
+    for seed in 0..NUM_CASE {
+        let mut simulator = random_simulator(seed);
+        assert_eq!(simulator.inject_faults(1.0), simulator.nodes.len());
+        let num_round = simulator.stabilize(MAX_ROUND);
+        assert!(num_round.is_some(), "seed {}", seed);
+        assert!(Verifier::new(&simulator.get_mis_result(), &graph_of(&simulator)).verify(), "seed {}", seed);
+        assert_unchanged(&mut simulator, 20, seed);
+    }
+}
+
+#[test]
+fn faults_converge_again() {
+    for seed in 0..NUM_CASE {
+        let mut simulator = random_simulator(seed);
+        simulator.inject_faults(1.0);
+        assert!(simulator.stabilize(MAX_ROUND).is_some(), "seed {}", seed);
+        for fault in 0..NUM_FAULT {
+            simulator.inject_faults(0.1);
+            assert!(simulator.stabilize(MAX_ROUND).is_some(), "seed {} fault {}", seed, fault);
+            assert!(Verifier::new(&simulator.get_mis_result(), &graph_of(&simulator)).verify(), "seed {} fault {}", seed, fault);
+            assert_unchanged(&mut simulator, 10, seed);
+        }
+    }
+}
+
+// a valid mis stays whatever the desire levels and the remembered neighbors hold
+#[test]
+fn valid_mis_with_corrupted_variables() {
+    // the path 0 - 1 - 2 - 3
+    let graph = vec![vec![1], vec![2], vec![3], vec![]];
+    let mut simulator = StabilizingSimulator::new(&graph, 0);
+    let states = [(true, 7.0, true), (false, -1.0, false), (false, 0.0, false), (true, 0.5, false)];
+    for (node, (is_in_mis, desire_level, has_mis_neighbor)) in simulator.nodes.iter_mut().zip(states.iter()) {
+        node.state = StabilizingState { is_in_mis: *is_in_mis, desire_level: *desire_level, has_mis_neighbor: *has_mis_neighbor };
+    }
+    assert!(simulator.is_stable());
+    assert_eq!(simulator.stabilize(MAX_ROUND), Some(0));
+    assert_unchanged(&mut simulator, 20, 0);
+    assert!(simulator.nodes.iter().all(|node| node.state.desire_level > 0.0 && node.state.desire_level <= 0.5));
+}
+
+// of two adjacent mis nodes the smaller id leaves
+#[test]
+fn adjacent_mis_nodes() {
+    let graph = vec![vec![1], vec![]];
+    let mut simulator = StabilizingSimulator::new(&graph, 0);
+    for node in simulator.nodes.iter_mut() {
+        node.state.is_in_mis = true;
+    }
+    assert!(!simulator.is_stable());
+    simulator.step();
+    assert!(!simulator.nodes[0].state.is_in_mis);
+    assert!(simulator.nodes[1].state.is_in_mis);
+    assert!(simulator.is_stable());
+    assert_eq!(simulator.history[0].removed, vec![0]);
+    assert_eq!(simulator.decide_round, vec![Some(0), None]);
+}
@@ -0,0 +1,96 @@
+// checks of the recorded traces: a threaded run replays without divergence, and a trace
+// changed anywhere is reported at the first event that no longer matches
+use distributed_mis::roles::byzantine::{Strategy, assign_strategies};
+use distributed_mis::roles::creater::{Creater};
+use distributed_mis::roles::desire::{DesireConfig};
+use distributed_mis::roles::node::{Algorithm, Message};
+use distributed_mis::roles::replayer::{Replayer};
+use distributed_mis::roles::runner::{RunConfig, run_threaded};
+use distributed_mis::roles::tracer::{Tracer, TraceHeader, TraceEvent, Endpoint, Payload, read_trace};
+use rand::{Rng, SeedableRng};
+use rand::prng::XorShiftRng;
+
+const NUM_CASE: u64 = 12;
+
+fn temp_path(name: &str, seed: u64) -> String {
+    std::env::temp_dir().join(format!("distributed_mis_{}_{}_{}", std::process::id(), name, seed)).to_string_lossy().to_string()
+}
+
+// records a run on a random graph and reads its trace back, with the mis of the run
+fn record(seed: u64) -> (TraceHeader, Vec<TraceEvent>, Vec<usize>) {
+    let mut rng = XorShiftRng::seed_from_u64(seed);
+    let mut creater = Creater::new();
+    let graph = creater.generate_with(rng.gen_range(10, 60), 5, &mut rng);
+    let algorithm = [Algorithm::Luby, Algorithm::Weighted, Algorithm::Metivier, Algorithm::Coloring, Algorithm::Matching][seed as usize % 5];
+    if algorithm == Algorithm::Weighted {
+        creater.random_weights_with(100, &mut rng);
+    }
+    let mut config = RunConfig::new(algorithm, seed);
+    config.strategies = vec![None; graph.len()];
+    if algorithm == Algorithm::Luby && seed.is_multiple_of(2) {
+        let mut desire = DesireConfig::default();
+        desire.apply("down=4,cap=0.25").unwrap();
+        config.desire = desire;
+        config.strategies = assign_strategies(graph.len(), 2, Strategy::FakeJoin, seed);
+        config.snapshot = Some((100, true));
+    }
+    let path = temp_path("trace", seed);
+    let tracer = Tracer::create(&path, &TraceHeader {
+        seed,
+        algorithm,
+        graph: graph.clone(),
+        weights: creater.weights.clone(),
+        stop_round: config.snapshot.map(|(round, _)| round),
+        strategies: config.strategies.clone(),
+        desire: config.desire,
+    }).unwrap();
+    config.tracer = Some(tracer.sender.clone());
+    let outcome = run_threaded(&graph, &creater.weights, &config);
+    tracer.finish().unwrap();
+    let (header, events) = read_trace(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    (header, events, outcome.coordinator.get_sorted_mis())
+}
+
+#[test]
+fn recorded_runs_replay() {
+    for seed in 0..NUM_CASE {
+        let (header, events, mis) = record(seed);
+        assert_eq!(events.last(), Some(&TraceEvent::End(mis.clone())), "seed {}", seed);
+        let mut replayer = Replayer::new(&header);
+        assert_eq!(replayer.run(&events, false).map_err(|d| d.step), Ok(events.len()), "seed {}", seed);
+        assert_eq!(replayer.coordinator.get_sorted_mis(), mis, "seed {}", seed);
+    }
+}
+
+// one event in the middle of the trace is changed in turn in each way below
+#[test]
+fn changed_traces_diverge_at_the_change() {
+    for seed in 0..NUM_CASE {
+        let (header, events, mis) = record(seed);
+        let deliveries: Vec<usize> = (0..events.len()).filter(|i| matches!(events[*i], TraceEvent::Delivery { to: Endpoint::Node(_), .. })).collect();
+        let changed = deliveries[deliveries.len() / 2];
+        let mut changes: Vec<(usize, TraceEvent)> = vec![];
+        if let TraceEvent::Delivery { round, from, to, payload } = events[changed].clone() {
+            // the message arrives, but the node records another round
+            changes.push((changed, TraceEvent::Delivery { round: round + 1, from, to, payload }));
+            // a message that was never sent
+            changes.push((changed, TraceEvent::Delivery { round, from, to, payload: Payload::Node(Message::Start(round + 7)) }));
+        }
+        let last = events.len() - 1;
+        let mut other_mis = mis.clone();
+        other_mis.push(header.graph.len());
+        changes.push((last, TraceEvent::End(other_mis)));
+        for (step, event) in changes {
+            let mut trace = events.clone();
+            trace[step] = event;
+            let divergence = Replayer::new(&header).run(&trace, false).unwrap_err();
+            assert_eq!(divergence.step, step, "seed {} {}", seed, divergence.found);
+            assert_eq!(divergence.expected, trace[step]);
+        }
+        // a missing delivery stays pending, the run diverges once it matters
+        let mut trace = events.clone();
+        trace.remove(changed);
+        assert!(Replayer::new(&header).run(&trace, false).unwrap_err().step >= changed, "seed {}", seed);
+    }
+}